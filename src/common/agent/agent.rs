@@ -2,20 +2,75 @@ use std::collections::HashSet;
 use rand_xoshiro::rand_core::{RngCore, SeedableRng};
 use rand_xoshiro::Xoshiro256PlusPlus;
 
+use crate::agent::behaviour::{Behaviour, RandomWalk};
 use crate::field::field::InstanceField;
+use crate::field::footprint_cells;
+
+/// What an agent does once its path is over
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PathEnd {
+    /// Stays on its last cell forever
+    Park,
+    /// Leaves the map
+    Vanish,
+    /// Starts the path over, the last cell must be adjacent to the first one
+    Loop,
+}
+
+impl PathEnd {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        return match name {
+            "park" => Ok(PathEnd::Park),
+            "vanish" => Ok(PathEnd::Vanish),
+            "loop" => Ok(PathEnd::Loop),
+            n => Err(format!("Unknown path end '{}', expected park, vanish or loop", n)),
+        };
+    }
+
+    pub fn name(&self) -> &'static str {
+        return match self {
+            PathEnd::Park => "park",
+            PathEnd::Vanish => "vanish",
+            PathEnd::Loop => "loop",
+        };
+    }
+}
 
 pub struct Agent {
     moves: Vec<(usize, usize)>,
     rng: Xoshiro256PlusPlus,
     stopped: bool,
+    behaviour: Box<dyn Behaviour>,
+    end: PathEnd,
+    /// Tick of the first cell of the path
+    start: usize,
+    /// Tick from which the agent has left the map, whatever its path end
+    until: Option<usize>,
+    /// Ticks between two steps of the path, the agent waiting on its cell in between
+    period: usize,
+    /// Ticks taken to reach the next cell, taking both cells in the meantime
+    move_ticks: usize,
+    /// Cells taken around each position of the path, see `footprint_cells`
+    footprint: (usize, usize),
 }
 
 impl Agent {
     pub fn new(seed: u64, start_pos: (usize, usize)) -> Self {
+        return Agent::with_behaviour(seed, start_pos, Box::new(RandomWalk));
+    }
+
+    pub fn with_behaviour(seed: u64, start_pos: (usize, usize), behaviour: Box<dyn Behaviour>) -> Self {
         return Agent {
             moves: vec![start_pos],
             rng: Xoshiro256PlusPlus::seed_from_u64(seed),
             stopped: false,
+            behaviour,
+            end: PathEnd::Park,
+            start: 0,
+            until: None,
+            period: 1,
+            move_ticks: 1,
+            footprint: (1, 1),
         };
     }
 
@@ -24,14 +79,114 @@ impl Agent {
             moves,
             rng: Xoshiro256PlusPlus::seed_from_u64(42), //we do not need it
             stopped: true,
+            behaviour: Box::new(RandomWalk),
+            end: PathEnd::Park,
+            start: 0,
+            until: None,
+            period: 1,
+            move_ticks: 1,
+            footprint: (1, 1),
+        };
+    }
+
+    pub fn with_end(mut self, end: PathEnd) -> Self {
+        self.end = end;
+        return self;
+    }
+
+    /// Present on the map during `[start, until)`, its path beginning at `start`
+    pub fn with_window(mut self, start: usize, until: Option<usize>) -> Self {
+        self.start = start;
+        self.until = until;
+        return self;
+    }
+
+    /// One step of the path every `period` ticks
+    pub fn with_period(mut self, period: usize) -> Self {
+        self.period = period.max(1);
+        return self;
+    }
+
+    /// Every move takes `move_ticks` ticks, the agent taking both cells until it arrives
+    pub fn with_move_ticks(mut self, move_ticks: usize) -> Self {
+        self.move_ticks = move_ticks.max(1);
+        return self;
+    }
+
+    /// Ticks between two steps of the path, a step lasting at least as long as a move
+    pub fn period(&self) -> usize {
+        return self.period.max(self.move_ticks);
+    }
+
+    pub fn move_ticks(&self) -> usize {
+        return self.move_ticks;
+    }
+
+    /// A `(width, height)` rectangle of cells anchored at the positions of the path
+    pub fn with_footprint(mut self, footprint: (usize, usize)) -> Self {
+        self.footprint = (footprint.0.max(1), footprint.1.max(1));
+        return self;
+    }
+
+    pub fn footprint(&self) -> (usize, usize) {
+        return self.footprint;
+    }
+
+    pub fn end(&self) -> PathEnd {
+        return self.end;
+    }
+
+    pub fn start(&self) -> usize {
+        return self.start;
+    }
+
+    pub fn until(&self) -> Option<usize> {
+        return self.until;
+    }
+
+    /// None while the agent is not on the map. A moving agent is on the cell it comes from
+    /// until it reaches the next one
+    pub fn get_pos(&self, time: usize) -> Option<(usize, usize)> {
+        return self.get_step(time).map(|(index, _)| self.moves[index]);
+    }
+
+    /// Cells taken at `time` by the footprint, at both ends of the move while moving
+    pub fn get_cells(&self, time: usize) -> Vec<(usize, usize)> {
+        let anchors = match self.get_step(time) {
+            Some((index, true)) => {
+                let (pos, next) = (self.moves[index], self.moves[(index + 1) % self.moves.len()]);
+                if next == pos { vec![pos] } else { vec![pos, next] }
+            }
+            Some((index, false)) => vec![self.moves[index]],
+            None => vec![],
         };
+        let moving = anchors.len() > 1;
+        let mut cells = anchors.into_iter().flat_map(|p| footprint_cells(p, self.footprint)).collect::<Vec<_>>();
+        if moving && self.footprint != (1, 1) {
+            // the two footprints may overlap
+            cells.sort_unstable();
+            cells.dedup();
+        }
+        return cells;
     }
 
-    pub fn get_pos(&self, time: usize) -> (usize, usize) {
-        if time >= self.moves.len() {
-            return self.get_last_pos();
+    /// Index of the current cell in the path, and whether the agent is moving to the next one
+    fn get_step(&self, time: usize) -> Option<(usize, bool)> {
+        if time < self.start || self.until.is_some_and(|u| time >= u) {
+            return None;
         }
-        return self.moves.get(time).cloned().unwrap();
+        let period = self.period();
+        let (step, phase) = ((time - self.start) / period, (time - self.start) % period);
+        // the move to the next cell fills the last ticks of the step
+        let moving = phase > period - self.move_ticks;
+        let len = self.moves.len();
+        return match self.end {
+            _ if step + 1 < len => Some((step, moving)),
+            PathEnd::Loop => Some((step % len, moving)),
+            PathEnd::Park => Some((len - 1, false)),
+            PathEnd::Vanish if step + 1 == len && phase == 0 => Some((step, false)),
+            PathEnd::Vanish => None,
+        };
     }
 
     pub fn get_last_pos(&self) -> (usize, usize) {
@@ -44,11 +199,7 @@ impl Agent {
         }
 
         let pos = self.get_last_pos();
-        let mut avail_moves: Vec<(usize, usize)> = field.iter_neighbors(pos.0, pos.1)
-            .filter(|x| !others.contains(x))
-            .collect::<Vec<(usize, usize)>>();
-
-        let mv: (usize, usize) = avail_moves.get(self.rng.next_u64() as usize % avail_moves.len()).cloned().unwrap();
+        let mv = self.behaviour.next_move(field, pos, others, &mut self.rng);
         self.moves.push(mv);
 
         if (self.rng.next_u32() as f64 / (u32::MAX as f64)) < stop_prob {
@@ -63,16 +214,34 @@ impl Agent {
     pub fn get_moves(&self) -> &Vec<(usize, usize)> {
         return &self.moves;
     }
+
+    /// Pads the path with waits up to `length` cells and walks it back to the second one,
+    /// so that it can loop. Agents retracing conflict free paths of the same length never
+    /// conflict along their loops, the way back being the way there reversed in time.
+    pub fn retrace(&mut self, length: usize) {
+        let last = self.get_last_pos();
+        self.moves.resize(length.max(self.moves.len()), last);
+        if self.moves.len() > 2 {
+            let back = self.moves[1..self.moves.len() - 1].iter().rev().cloned().collect::<Vec<_>>();
+            self.moves.extend(back);
+        }
+    }
 }
 
 pub fn get_agents_at_time(agents: &Vec<Agent>, time: usize) -> HashSet<(usize, usize)> {
     return agents.iter()
-        .map(|x| x.get_pos(time))
+        .flat_map(|x| x.get_cells(time))
         .collect::<HashSet<(usize, usize)>>();
 }
 
+/// Cells the agents keep taking once their paths are over
 pub fn get_agents_last(agents: &Vec<Agent>) -> HashSet<(usize, usize)> {
     return agents.iter()
-        .map(|x| x.get_last_pos())
+        .filter(|x| x.until.is_none())
+        .flat_map(|x| match x.end {
+            PathEnd::Park => vec![x.get_last_pos()],
+            PathEnd::Vanish => vec![],
+            PathEnd::Loop => x.moves.clone(),
+        }.into_iter().flat_map(|p| footprint_cells(p, x.footprint)))
         .collect::<HashSet<(usize, usize)>>();
 }
@@ -1,29 +1,162 @@
-use crate::agent::agent::Agent;
+use std::collections::{HashMap, HashSet};
 
+use crate::agent::agent::{Agent, PathEnd};
+use crate::field::closure::Closures;
+use crate::field::footprint_cells;
+
+/// A move `(from, to, time)` between `time` and `time + 1`
+type Edge = ((usize, usize), (usize, usize), usize);
+/// A cell of a looping agent, `(cell, period, time % period)`
+type CyclicVertex = ((usize, usize), usize, usize);
+/// A move of a looping agent, `(from, to, period, time % period)`
+type CyclicEdge = ((usize, usize), (usize, usize), usize, usize);
+/// Ticks `[from, until)` an agent is on the map
+type Window = (usize, Option<usize>);
+
+fn within(windows: Option<&Vec<Window>>, time: usize) -> bool {
+    return windows.is_some_and(|w| w.iter().any(|(from, until)| time >= *from && until.is_none_or(|u| time < u)));
+}
+
+/// Space-time reservations of the agents, indexed once so that every query is O(1) per cell
+/// of the planned agent's footprint, or O(number of distinct loop lengths) when agents loop. Cells taken for an unbounded time,
+/// by parked and looping agents, are stored with the window they are taken in.
 pub struct AgentManager {
-    agents: Vec<Agent>,
+    /// Cells taken while the agents move
+    vertices: HashSet<((usize, usize), usize)>,
+    /// Waits excluded
+    edges: HashSet<Edge>,
+    /// Windows a cell is taken in by parked agents
+    parked: HashMap<(usize, usize), Vec<Window>>,
+    cycles: HashMap<CyclicVertex, Vec<Window>>,
+    cyclic_edges: HashMap<CyclicEdge, Vec<Window>>,
+    periods: Vec<usize>,
+    closures: Closures,
+    /// Footprint of the planned agent
+    footprint: (usize, usize),
 }
 
 impl AgentManager {
     pub fn new(agents: Vec<Agent>) -> Self {
-        return AgentManager { agents };
+        return AgentManager::with_closures(agents, Closures::new(vec![]));
     }
 
-    pub fn is_traversable(&self, frm: (usize, usize), to: (usize, usize), time: usize) -> bool {
-        for a in &self.agents {
-            if a.get_pos(time + 1) == to || (a.get_pos(time + 1) == frm && a.get_pos(time) == to) {
-                return false;
+    /// Cells closed by `closures` are treated as occupied while their window is active
+    pub fn with_closures(agents: Vec<Agent>, closures: Closures) -> Self {
+        let agents = agents.iter().filter(|a| !a.get_moves().is_empty()).collect::<Vec<&Agent>>();
+        let ticks = agents.iter().map(|a| a.get_moves().len() * a.period() * a.footprint().0 * a.footprint().1).sum();
+        let mut mgr = AgentManager {
+            vertices: HashSet::with_capacity(ticks),
+            edges: HashSet::new(),
+            parked: HashMap::new(),
+            cycles: HashMap::new(),
+            cyclic_edges: HashMap::new(),
+            periods: vec![],
+            closures,
+            footprint: (1, 1),
+        };
+        for a in agents {
+            let moves = a.get_moves();
+            let (start, until, k) = (a.start(), a.until(), a.period());
+            let present = |t: usize| until.is_none_or(|u| t < u);
+            // whether the agent is on its way to the next cell `j` ticks into a step
+            let moving = |j: usize| j > k - a.move_ticks();
+            let cells = |p: (usize, usize)| footprint_cells(p, a.footprint());
+            // the footprint cells of both ends of a move, in the same order
+            let steps = |p: (usize, usize), q: (usize, usize)| cells(p).zip(cells(q));
+            let last = moves.len() - 1;
+            match a.end() {
+                PathEnd::Loop => {
+                    let period = moves.len() * k;
+                    for (i, pos) in moves.iter().enumerate() {
+                        let next = moves[(i + 1) % moves.len()];
+                        for j in 0..k {
+                            let phase = (start + i * k + j) % period;
+                            let taken = if moving(j) && next != *pos { vec![*pos, next] } else { vec![*pos] };
+                            for c in taken.into_iter().flat_map(cells) {
+                                mgr.cycles.entry((c, period, phase)).or_default().push((start, until));
+                            }
+                        }
+                        if next != *pos {
+                            // the move ends at `phase + 1`, both ends must be in the window
+                            let phase = (start + i * k + k - 1) % period;
+                            for (from, to) in steps(*pos, next) {
+                                mgr.cyclic_edges.entry((from, to, period, phase)).or_default().push((start, until.map(|u| u.saturating_sub(1))));
+                            }
+                        }
+                    }
+                    if !mgr.periods.contains(&period) {
+                        mgr.periods.push(period);
+                    }
+                }
+                end => {
+                    'path: for (i, pos) in moves[..last].iter().enumerate() {
+                        let next = moves[i + 1];
+                        for t in start + i * k..start + (i + 1) * k {
+                            if !present(t) {
+                                break 'path;
+                            }
+                            mgr.vertices.extend(cells(*pos).map(|c| (c, t)));
+                            if moving(t - start - i * k) && next != *pos {
+                                mgr.vertices.extend(cells(next).map(|c| (c, t)));
+                            }
+                        }
+                        if next != *pos && present(start + (i + 1) * k) {
+                            mgr.edges.extend(steps(*pos, next).map(|(from, to)| (from, to, start + (i + 1) * k - 1)));
+                        }
+                    }
+                    if !present(start + last * k) {
+                        continue;
+                    }
+                    if end == PathEnd::Park {
+                        for c in cells(moves[last]) {
+                            mgr.parked.entry(c).or_default().push((start + last * k, until));
+                        }
+                    } else {
+                        mgr.vertices.extend(cells(moves[last]).map(|c| (c, start + last * k)));
+                    }
+                }
             }
         }
-        return true;
+        return mgr;
+    }
+
+    /// The planned agent takes the `(width, height)` rectangle of cells anchored at its position
+    pub fn with_footprint(mut self, footprint: (usize, usize)) -> Self {
+        self.footprint = (footprint.0.max(1), footprint.1.max(1));
+        return self;
+    }
+
+    fn is_occupied(&self, pos: (usize, usize), time: usize) -> bool {
+        return self.vertices.contains(&(pos, time))
+            || within(self.parked.get(&pos), time)
+            || self.periods.iter().any(|p| within(self.cycles.get(&(pos, *p, time % p)), time));
+    }
+
+    fn is_free(&self, pos: (usize, usize), time: usize) -> bool {
+        return footprint_cells(pos, self.footprint).all(|c| !self.closures.is_closed(c, time) && !self.is_occupied(c, time));
+    }
+
+    pub fn is_traversable(&self, frm: (usize, usize), to: (usize, usize), time: usize) -> bool {
+        if !self.is_free(to, time + 1) {
+            return false;
+        }
+        // parked agents never swap, their cell is taken at `time + 1` anyway
+        return footprint_cells(frm, self.footprint).zip(footprint_cells(to, self.footprint)).all(|(frm, to)| {
+            !self.edges.contains(&(to, frm, time))
+                && !self.periods.iter().any(|p| within(self.cyclic_edges.get(&(to, frm, *p, time % p)), time))
+        });
     }
 
     pub fn can_stay(&self, pos: (usize, usize), time: usize) -> bool {
-        for a in &self.agents {
-            if a.get_pos(time) == pos {
-                return false;
-            }
+        return self.is_free(pos, time);
+    }
+
+    /// A move taking `ticks` ticks from `time`, both cells being taken until `time + ticks`
+    pub fn is_traversable_in(&self, frm: (usize, usize), to: (usize, usize), time: usize, ticks: usize) -> bool {
+        if ticks <= 1 || frm == to {
+            return self.is_traversable(frm, to, time);
         }
-        return true;
+        return (time + 1..time + ticks).all(|t| self.can_stay(frm, t) && self.can_stay(to, t))
+            && self.is_traversable(frm, to, time + ticks - 1);
     }
-}
\ No newline at end of file
+}
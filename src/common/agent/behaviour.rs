@@ -0,0 +1,219 @@
+//! Agent behaviour models
+//!
+//! A behaviour picks the next cell of an agent among the free neighbours of its current
+//! position, staying in place being always allowed. Agents following a planned route wait
+//! when the next cell is taken by another agent and, after `MAX_BLOCKED` ticks, step
+//! aside randomly and plan again.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::rc::Rc;
+
+use rand_xoshiro::rand_core::RngCore;
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use crate::field::components::Components;
+use crate::field::connectivity::Connectivity;
+use crate::field::field::{Field, InstanceField};
+
+const MAX_BLOCKED: usize = 3;
+/// Random cells tried when looking for a reachable destination
+const PICK_ATTEMPTS: usize = 64;
+
+/// Moves from the start and previous cell of every reached cell
+type PathNodes = HashMap<(usize, usize), (usize, Option<(usize, usize)>)>;
+/// Open cells as `(bound, moves, cell)`, smallest bound first
+type PathHeap = BinaryHeap<Reverse<(usize, usize, (usize, usize))>>;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BehaviourModel {
+    Random,
+    Destination,
+    Patrol,
+}
+
+impl BehaviourModel {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        return match name {
+            "random" => Ok(BehaviourModel::Random),
+            "destination" => Ok(BehaviourModel::Destination),
+            "patrol" => Ok(BehaviourModel::Patrol),
+            n => Err(format!("Unknown agent model '{}', expected random, destination or patrol", n))
+        };
+    }
+
+    pub fn name(&self) -> &'static str {
+        return match self {
+            BehaviourModel::Random => "random",
+            BehaviourModel::Destination => "destination",
+            BehaviourModel::Patrol => "patrol",
+        };
+    }
+}
+
+pub trait Behaviour {
+    /// Next cell of an agent standing in `pos`, `others` being the cells taken by the other agents
+    fn next_move(&mut self, field: &InstanceField, pos: (usize, usize), others: &HashSet<(usize, usize)>, rng: &mut Xoshiro256PlusPlus) -> (usize, usize);
+}
+
+fn probability(rng: &mut Xoshiro256PlusPlus) -> f64 {
+    return rng.next_u32() as f64 / (u32::MAX as f64);
+}
+
+/// Uniform pick among the free neighbours
+pub struct RandomWalk;
+
+impl Behaviour for RandomWalk {
+    fn next_move(&mut self, field: &InstanceField, pos: (usize, usize), others: &HashSet<(usize, usize)>, rng: &mut Xoshiro256PlusPlus) -> (usize, usize) {
+        let avail_moves: Vec<(usize, usize)> = field.iter_neighbors(pos.0, pos.1)
+            .filter(|x| !others.contains(x))
+            .collect::<Vec<(usize, usize)>>();
+        return avail_moves.get(rng.next_u64() as usize % avail_moves.len()).cloned().unwrap();
+    }
+}
+
+/// Stays in place with probability `wait_probability`, otherwise moves as `inner`
+pub struct Lazy {
+    inner: Box<dyn Behaviour>,
+    wait_probability: f64,
+}
+
+impl Lazy {
+    pub fn new(inner: Box<dyn Behaviour>, wait_probability: f64) -> Self {
+        return Lazy { inner, wait_probability };
+    }
+}
+
+impl Behaviour for Lazy {
+    fn next_move(&mut self, field: &InstanceField, pos: (usize, usize), others: &HashSet<(usize, usize)>, rng: &mut Xoshiro256PlusPlus) -> (usize, usize) {
+        if probability(rng) < self.wait_probability {
+            return pos;
+        }
+        return self.inner.next_move(field, pos, others, rng);
+    }
+}
+
+#[derive(Default)]
+struct Route {
+    plan: VecDeque<(usize, usize)>,
+    blocked: usize,
+}
+
+impl Route {
+    /// `None` once the route is over
+    fn step(&mut self, field: &InstanceField, pos: (usize, usize), others: &HashSet<(usize, usize)>, rng: &mut Xoshiro256PlusPlus) -> Option<(usize, usize)> {
+        let next = *self.plan.front()?;
+        if !others.contains(&next) {
+            self.blocked = 0;
+            return self.plan.pop_front();
+        }
+        self.blocked += 1;
+        if self.blocked <= MAX_BLOCKED {
+            return Some(pos);
+        }
+        self.blocked = 0;
+        self.plan.clear();
+        return Some(RandomWalk.next_move(field, pos, others, rng));
+    }
+}
+
+/// Walks along shortest paths to random destinations of its connected component
+pub struct Destination {
+    components: Rc<Components>,
+    route: Route,
+}
+
+impl Destination {
+    pub fn new(components: Rc<Components>) -> Self {
+        return Destination { components, route: Route::default() };
+    }
+}
+
+impl Behaviour for Destination {
+    fn next_move(&mut self, field: &InstanceField, pos: (usize, usize), others: &HashSet<(usize, usize)>, rng: &mut Xoshiro256PlusPlus) -> (usize, usize) {
+        if self.route.plan.is_empty() {
+            for _ in 0..PICK_ATTEMPTS {
+                let target = ((rng.next_u64() % field.width() as u64) as usize, (rng.next_u64() % field.height() as u64) as usize);
+                if target != pos && self.components.connected(pos, target) {
+                    self.route.plan = shortest_path(field, pos, target).unwrap_or_default();
+                    break;
+                }
+            }
+        }
+        return self.route.step(field, pos, others, rng).unwrap_or(pos);
+    }
+}
+
+/// Cycles through `waypoints`, which must be reachable from the starting cell
+pub struct Patrol {
+    waypoints: Vec<(usize, usize)>,
+    next: usize,
+    route: Route,
+}
+
+impl Patrol {
+    /// Heads to `waypoints[first]` first
+    pub fn new(waypoints: Vec<(usize, usize)>, first: usize) -> Self {
+        let next = if waypoints.is_empty() { 0 } else { first % waypoints.len() };
+        return Patrol { waypoints, next, route: Route::default() };
+    }
+}
+
+impl Behaviour for Patrol {
+    fn next_move(&mut self, field: &InstanceField, pos: (usize, usize), others: &HashSet<(usize, usize)>, rng: &mut Xoshiro256PlusPlus) -> (usize, usize) {
+        if self.waypoints.is_empty() {
+            return RandomWalk.next_move(field, pos, others, rng);
+        }
+        if self.route.plan.is_empty() {
+            for _ in 0..self.waypoints.len() {
+                let target = self.waypoints[self.next];
+                self.next = (self.next + 1) % self.waypoints.len();
+                if target != pos {
+                    self.route.plan = shortest_path(field, pos, target).unwrap_or_default();
+                    break;
+                }
+            }
+        }
+        return self.route.step(field, pos, others, rng).unwrap_or(pos);
+    }
+}
+
+/// Fewest moves from `from` to `to` ignoring agents and closures, `from` excluded
+pub fn shortest_path(field: &InstanceField, from: (usize, usize), to: (usize, usize)) -> Option<VecDeque<(usize, usize)>> {
+    // lower bound on the moves left, a 16-neighbour move covers at most 2 cells per axis
+    let bound = |p: &(usize, usize)| {
+        let (dx, dy) = field.delta(p, &to);
+        let (dx, dy) = (dx.unsigned_abs() as usize, dy.unsigned_abs() as usize);
+        return match field.connectivity() {
+            Connectivity::Four => dx + dy,
+            Connectivity::Eight => dx.max(dy),
+            Connectivity::Sixteen => dx.max(dy).div_ceil(2),
+        };
+    };
+
+    let mut nodes: PathNodes = HashMap::new();
+    let mut heap: PathHeap = BinaryHeap::new();
+    nodes.insert(from, (0, None));
+    heap.push(Reverse((bound(&from), 0, from)));
+    while let Some(Reverse((_, g, node))) = heap.pop() {
+        if node == to {
+            let mut path = VecDeque::with_capacity(g);
+            let mut cur = to;
+            while let Some(prev) = nodes[&cur].1 {
+                path.push_front(cur);
+                cur = prev;
+            }
+            return Some(path);
+        }
+        if g > nodes[&node].0 {
+            continue;
+        }
+        for adj in field.iter_neighbors(node.0, node.1) {
+            if nodes.get(&adj).is_none_or(|n| g + 1 < n.0) {
+                nodes.insert(adj, (g + 1, Some(node)));
+                heap.push(Reverse((g + 1 + bound(&adj), g + 1, adj)));
+            }
+        }
+    }
+    return None;
+}
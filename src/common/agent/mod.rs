@@ -1,2 +1,185 @@
 pub mod agent;
 pub mod agent_manager;
+pub mod behaviour;
+pub mod validate;
+
+#[cfg(test)]
+mod agent_manager_test {
+    use crate::agent::agent::{Agent, PathEnd};
+    use crate::agent::agent_manager::AgentManager;
+
+    #[test]
+    fn reservations() {
+        let am = AgentManager::new(vec![Agent::from(vec![(0, 0), (1, 0), (1, 0), (2, 0)]), Agent::from(vec![(3, 3)])]);
+        assert!(!am.can_stay((0, 0), 0) && am.can_stay((0, 0), 1));
+        assert!(!am.can_stay((1, 0), 2) && am.can_stay((1, 0), 3));
+        // parked forever on the last cell
+        assert!(am.can_stay((2, 0), 2) && !am.can_stay((2, 0), 3) && !am.can_stay((2, 0), 1000));
+        assert!(!am.can_stay((3, 3), 0) && !am.can_stay((3, 3), 50));
+
+        // swapping with the first agent along its first move
+        assert!(!am.is_traversable((1, 0), (0, 0), 0));
+        assert!(am.is_traversable((1, 0), (0, 0), 1));
+        assert!(!am.is_traversable((3, 0), (2, 0), 5));
+
+        let ends = |end: PathEnd| AgentManager::new(vec![Agent::from(vec![(0, 0), (1, 0), (2, 0)]).with_end(end)]);
+        let vanish = ends(PathEnd::Vanish);
+        assert!(!vanish.can_stay((2, 0), 2) && vanish.can_stay((2, 0), 3));
+        let cycle = ends(PathEnd::Loop);
+        assert!(!cycle.can_stay((0, 0), 3) && !cycle.can_stay((1, 0), 7) && cycle.can_stay((2, 0), 3));
+        // the step from the last cell back to the first one
+        assert!(!cycle.is_traversable((0, 0), (2, 0), 5));
+
+        // on the map during [2, 4) only
+        let late = AgentManager::new(vec![Agent::from(vec![(0, 0), (1, 0), (2, 0)]).with_window(2, Some(4))]);
+        assert!(late.can_stay((0, 0), 0) && !late.can_stay((0, 0), 2) && !late.can_stay((1, 0), 3));
+        assert!(late.can_stay((2, 0), 4) && late.can_stay((1, 0), 4));
+        assert!(!late.is_traversable((1, 0), (0, 0), 2) && late.is_traversable((2, 0), (1, 0), 3));
+    }
+
+    #[test]
+    fn periods() {
+        // one step every 2 ticks, waiting on the cell and moving in a single tick
+        let slow = Agent::from(vec![(0, 0), (1, 0), (2, 0)]).with_period(2);
+        assert_eq!(vec![(0, 0)], slow.get_cells(1));
+        assert_eq!(Some((1, 0)), slow.get_pos(2));
+        let am = AgentManager::new(vec![slow]);
+        assert!(!am.can_stay((0, 0), 1) && am.can_stay((1, 0), 1) && !am.can_stay((1, 0), 3));
+        assert!(am.can_stay((0, 0), 2) && !am.can_stay((2, 0), 4));
+        // the moves happen between ticks 1 and 2, then 3 and 4
+        assert!(!am.is_traversable((1, 0), (0, 0), 1) && !am.is_traversable((2, 0), (1, 0), 3));
+        assert!(am.is_traversable((1, 1), (0, 0), 1));
+    }
+
+    #[test]
+    fn move_ticks() {
+        let slow = Agent::from(vec![(0, 0), (1, 0), (2, 0)]).with_move_ticks(3);
+        assert_eq!(Some((1, 0)), slow.get_pos(4));
+        assert_eq!(vec![(1, 0), (2, 0)], slow.get_cells(4));
+
+        // both ends of a move are taken until its arrival tick
+        let am = AgentManager::new(vec![slow]);
+        assert!(am.can_stay((1, 0), 0) && !am.can_stay((1, 0), 1) && am.can_stay((0, 0), 3));
+        assert!(!am.can_stay((2, 0), 4) && !am.can_stay((2, 0), 6));
+        assert!(am.is_traversable_in((3, 0), (2, 0), 0, 3));
+        assert!(!am.is_traversable_in((3, 0), (2, 0), 1, 3));
+    }
+
+    #[test]
+    fn footprints() {
+        // a 2x2 agent parked on (1, 1)..=(2, 2)
+        let parked = || Agent::from(vec![(1, 1)]).with_footprint((2, 2));
+        let am = AgentManager::new(vec![parked()]);
+        assert!(!am.can_stay((2, 2), 0) && am.can_stay((3, 3), 0));
+        let large = AgentManager::new(vec![parked()]).with_footprint((2, 2));
+        assert!(!large.can_stay((0, 0), 0) && large.can_stay((3, 0), 0));
+        assert!(!large.is_traversable((3, 0), (2, 0), 0));
+
+        // the lower cell of a 1x2 agent stepping right swaps with an agent stepping left
+        let crossing = || vec![Agent::from(vec![(1, 1), (0, 1)])];
+        assert!(AgentManager::new(crossing()).is_traversable((0, 0), (1, 0), 0));
+        assert!(!AgentManager::new(crossing()).with_footprint((1, 2)).is_traversable((0, 0), (1, 0), 0));
+    }
+}
+
+#[cfg(test)]
+mod behaviour_test {
+    use std::collections::HashSet;
+    use std::rc::Rc;
+
+    use crate::agent::agent::Agent;
+    use crate::agent::behaviour::{shortest_path, Destination, Lazy, Patrol};
+    use crate::field::components::Components;
+    use crate::field::connectivity::Connectivity;
+    use crate::field::field::CustomField;
+
+    #[test]
+    fn patrol() {
+        // a wall splitting the grid but for its bottom row
+        let mut f = CustomField::new(1, (3, 3), vec![(1, 0), (1, 1)]);
+        f.set_connectivity(Connectivity::Four);
+        assert_eq!(vec![(0, 1), (0, 2), (1, 2), (2, 2), (2, 1), (2, 0)], shortest_path(&f, (0, 0), (2, 0)).unwrap().into_iter().collect::<Vec<_>>());
+        assert_eq!(None, shortest_path(&f, (0, 0), (1, 0)));
+
+        let mut a = Agent::with_behaviour(1, (0, 0), Box::new(Patrol::new(vec![(2, 0), (0, 0)], 0)));
+        for _ in 0..12 {
+            a.next_move(&f, &HashSet::new(), 0.0);
+        }
+        assert_eq!(Some((2, 0)), a.get_pos(6));
+        assert_eq!(Some((0, 0)), a.get_pos(12));
+
+        // waits behind a parked agent, then steps aside
+        let mut b = Agent::with_behaviour(1, (0, 0), Box::new(Patrol::new(vec![(0, 2)], 0)));
+        for _ in 0..3 {
+            b.next_move(&f, &HashSet::from([(0, 1)]), 0.0);
+        }
+        assert_eq!(vec![(0, 0); 4], *b.get_moves());
+    }
+
+    #[test]
+    fn destination() {
+        // ..#..
+        // ..#..
+        // ..#..
+        let mut f = CustomField::new(1, (5, 3), vec![(2, 0), (2, 1), (2, 2)]);
+        f.set_connectivity(Connectivity::Four);
+        let components = Rc::new(Components::new(&f));
+
+        let mut a = Agent::with_behaviour(3, (0, 0), Box::new(Destination::new(components.clone())));
+        for _ in 0..50 {
+            a.next_move(&f, &HashSet::new(), 0.0);
+        }
+        // targets are only picked in the left half, which the agent never leaves
+        assert!(a.get_moves().iter().all(|p| components.connected((0, 0), *p)));
+        assert!(a.get_moves().iter().any(|p| *p != (0, 0)));
+    }
+
+    #[test]
+    fn lazy() {
+        let mut f = CustomField::new(1, (3, 3), vec![]);
+        f.set_connectivity(Connectivity::Four);
+
+        let mut still = Agent::with_behaviour(1, (0, 0), Box::new(Lazy::new(Box::new(Patrol::new(vec![(2, 2)], 0)), 1.0)));
+        let mut eager = Agent::with_behaviour(1, (0, 0), Box::new(Lazy::new(Box::new(Patrol::new(vec![(2, 2)], 0)), 0.0)));
+        let mut lazy = Agent::with_behaviour(1, (0, 0), Box::new(Lazy::new(Box::new(Patrol::new(vec![(2, 2), (0, 0)], 0)), 0.5)));
+        for _ in 0..400 {
+            still.next_move(&f, &HashSet::new(), 0.0);
+            eager.next_move(&f, &HashSet::new(), 0.0);
+            lazy.next_move(&f, &HashSet::new(), 0.0);
+        }
+        assert!(still.get_moves().iter().all(|p| *p == (0, 0)));
+        assert_eq!(Some((2, 2)), eager.get_pos(4));
+
+        // the patrol never waits on its own, so about half of the ticks are waits
+        let waits = lazy.get_moves().windows(2).filter(|w| w[0] == w[1]).count();
+        assert!((150..250).contains(&waits), "{} waits", waits);
+    }
+}
+
+#[cfg(test)]
+mod validate_test {
+    use crate::agent::agent::{Agent, PathEnd};
+    use crate::agent::validate::{validate_paths, PathError};
+    use crate::field::field::CustomField;
+
+    #[test]
+    fn conflicts() {
+        let f = CustomField::new(1, (3, 2), vec![(2, 1)]);
+        let agents = |paths: Vec<Vec<(usize, usize)>>| paths.into_iter().map(Agent::from).collect::<Vec<Agent>>();
+        assert_eq!(Ok(()), validate_paths(&f, &agents(vec![vec![(0, 0), (1, 0), (2, 0)], vec![(1, 1), (0, 0)]]), 10));
+        // agent 1 parks on the cell agent 0 reaches last
+        assert_eq!(Err(PathError::Vertex { agents: (0, 1), tick: 2, cell: (1, 1) }),
+                   validate_paths(&f, &agents(vec![vec![(0, 0), (0, 1), (1, 1)], vec![(2, 0), (1, 1)]]), 0));
+        let swap = validate_paths(&f, &agents(vec![vec![(0, 0), (0, 0), (1, 0)], vec![(1, 0), (1, 0), (0, 0)]]), 0).unwrap_err();
+        assert_eq!(PathError::Swap { agents: (0, 1), tick: 2, cells: ((0, 0), (1, 0)) }, swap);
+        assert_eq!(2, swap.tick());
+        assert_eq!(Err(PathError::Jump { agent: 0, tick: 1, from: (0, 0), to: (2, 0) }), validate_paths(&f, &agents(vec![vec![(0, 0), (2, 0)]]), 0));
+        assert_eq!(Err(PathError::Blocked { agent: 0, tick: 1, cell: (2, 1) }), validate_paths(&f, &agents(vec![vec![(1, 0), (2, 1)]]), 0));
+
+        // a vanished agent frees its cell, a looping one comes back to its start
+        let ends = |end: PathEnd| vec![Agent::from(vec![(0, 0), (1, 0)]).with_end(end), Agent::from(vec![(0, 1), (1, 1), (1, 0)])];
+        assert_eq!(Err(PathError::Vertex { agents: (0, 1), tick: 2, cell: (1, 0) }), validate_paths(&f, &ends(PathEnd::Park), 5));
+        assert_eq!(Ok(()), validate_paths(&f, &ends(PathEnd::Vanish), 5));
+        assert_eq!(Err(PathError::Vertex { agents: (0, 1), tick: 3, cell: (1, 0) }), validate_paths(&f, &ends(PathEnd::Loop), 5));
+    }
+}
@@ -0,0 +1,101 @@
+//! Consistency checks of the agents' trajectories
+//!
+//! Paths follow the rules of `AgentManager`: two agents never share a cell at the same
+//! tick and never swap cells along one move. Every move must be a step of the field's
+//! neighbour iterator, waits included, looping agents stepping from their last cell back
+//! to the first one. Agents whose moves last several ticks take both ends of a move until
+//! they reach the next cell, and agents larger than a cell take every cell of their footprint.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use crate::agent::agent::Agent;
+use crate::field::field::{Field, InstanceField};
+use crate::field::footprint_cells;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathError {
+    Empty { agent: usize },
+    Blocked { agent: usize, tick: usize, cell: (usize, usize) },
+    Jump { agent: usize, tick: usize, from: (usize, usize), to: (usize, usize) },
+    Vertex { agents: (usize, usize), tick: usize, cell: (usize, usize) },
+    Swap { agents: (usize, usize), tick: usize, cells: ((usize, usize), (usize, usize)) },
+}
+
+impl PathError {
+    /// Tick of the offending position, moves are reported at their arrival tick
+    pub fn tick(&self) -> usize {
+        return match self {
+            PathError::Empty { .. } => 0,
+            PathError::Blocked { tick, .. } | PathError::Jump { tick, .. } | PathError::Vertex { tick, .. } | PathError::Swap { tick, .. } => *tick,
+        };
+    }
+}
+
+impl Display for PathError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        return match self {
+            PathError::Empty { agent } => write!(f, "agent {} has an empty path", agent),
+            PathError::Blocked { agent, tick, cell } => write!(f, "tick {}: agent {} is on {:?}, outside the grid or on an obstacle", tick, agent, cell),
+            PathError::Jump { agent, tick, from, to } => write!(f, "tick {}: agent {} moves from {:?} to {:?}, which are not adjacent", tick, agent, from, to),
+            PathError::Vertex { agents, tick, cell } => write!(f, "tick {}: agents {} and {} are both on {:?}", tick, agents.0, agents.1, cell),
+            PathError::Swap { agents, tick, cells } => write!(f, "tick {}: agents {} and {} swap {:?} and {:?}", tick, agents.0, agents.1, cells.0, cells.1),
+        };
+    }
+}
+
+/// A footprint cell moving `(from, to)`
+type CellMove = ((usize, usize), (usize, usize));
+
+/// Moves of the footprint cells of `agent` when it steps from `from` to `to`
+fn cell_moves(agent: &Agent, from: (usize, usize), to: (usize, usize)) -> impl Iterator<Item = CellMove> {
+    return footprint_cells(from, agent.footprint()).zip(footprint_cells(to, agent.footprint())).filter(|(f, t)| f != t);
+}
+
+/// Reports the first conflict in tick order, up to `horizon` or the end of the longest path
+pub fn validate_paths(field: &InstanceField, agents: &[Agent], horizon: usize) -> Result<(), PathError> {
+    if let Some(agent) = agents.iter().position(|a| a.get_moves().is_empty()) {
+        return Err(PathError::Empty { agent });
+    }
+    let ticks = agents.iter().map(|a| a.start() + a.get_moves().len() * a.period()).max().unwrap_or(0).max(horizon + 1);
+
+    let mut current: HashMap<(usize, usize), usize> = HashMap::with_capacity(agents.len());
+    // footprint cells moving between the previous tick and the current one
+    let mut moves: HashMap<CellMove, usize> = HashMap::with_capacity(agents.len());
+    for tick in 0..ticks {
+        current.clear();
+        moves.clear();
+        for (agent, a) in agents.iter().enumerate() {
+            let cells = a.get_cells(tick);
+            for cell in cells.iter().cloned() {
+                if !field.exists(cell.0, cell.1) || field.is_obstacle(cell.0, cell.1) {
+                    return Err(PathError::Blocked { agent, tick, cell });
+                }
+            }
+            if let (Some(from), Some(to)) = (tick.checked_sub(1).and_then(|t| a.get_pos(t)), a.get_pos(tick)) {
+                if !field.iter_neighbors_of(from.0, from.1, a.footprint()).any(|n| n == to) {
+                    return Err(PathError::Jump { agent, tick, from, to });
+                }
+                moves.extend(cell_moves(a, from, to).map(|m| (m, agent)));
+            }
+            for cell in cells {
+                if let Some(other) = current.insert(cell, agent) {
+                    return Err(PathError::Vertex { agents: (other, agent), tick, cell });
+                }
+            }
+        }
+        for (agent, a) in agents.iter().enumerate() {
+            if let (Some(from), Some(to)) = (tick.checked_sub(1).and_then(|t| a.get_pos(t)), a.get_pos(tick)) {
+                for (from, to) in cell_moves(a, from, to) {
+                    match moves.get(&(to, from)) {
+                        Some(&other) if other != agent => {
+                            return Err(PathError::Swap { agents: (other.min(agent), other.max(agent)), tick, cells: (from, to) });
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+    return Ok(());
+}
@@ -0,0 +1,65 @@
+//! ASCII art fields
+//!
+//! Reads back the `#`/`.` maps printed by the fields' `Display`, one character per
+//! cell. `S` marks the init position, `G` the goal and lowercase letters the starting
+//! cells of agents, ordered by letter. Marked cells are free. The `Field(WxH)...`
+//! header line printed by `Display` is skipped.
+
+use crate::field::field::{CustomField, InstanceField};
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AsciiMarkers {
+    pub init: Option<(usize, usize)>,
+    pub goal: Option<(usize, usize)>,
+    /// Starting cells of the agents `a`, `b`, ... in letter order
+    pub agents: Vec<(usize, usize)>,
+}
+
+pub struct AsciiMap {
+    pub field: InstanceField,
+    pub markers: AsciiMarkers,
+}
+
+pub fn parse_ascii(contents: &str, seed: u64) -> Result<AsciiMap, String> {
+    let mut obstacles = Vec::new();
+    let mut markers = AsciiMarkers::default();
+    let mut agents: Vec<(char, (usize, usize))> = Vec::new();
+    let mut width: Option<usize> = None;
+    let mut y = 0;
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("Field(") {
+            continue;
+        }
+        let w = line.chars().count();
+        if *width.get_or_insert(w) != w {
+            return Err(format!("Row {} is {} cells wide, expected {}", y, w, width.unwrap()));
+        }
+        for (x, c) in line.chars().enumerate() {
+            match c {
+                '.' => {}
+                '#' => obstacles.push((x, y)),
+                'S' if markers.init.is_none() => markers.init = Some((x, y)),
+                'G' if markers.goal.is_none() => markers.goal = Some((x, y)),
+                'S' | 'G' => return Err(format!("'{}' is marked more than once", c)),
+                c if c.is_ascii_lowercase() => {
+                    if agents.iter().any(|a| a.0 == c) {
+                        return Err(format!("Agent '{}' is marked more than once", c));
+                    }
+                    agents.push((c, (x, y)));
+                }
+                c => return Err(format!("Unexpected character '{}' at ({}, {})", c, x, y)),
+            }
+        }
+        y += 1;
+    }
+
+    let width = width.ok_or("Empty ascii field".to_string())?;
+    agents.sort_by_key(|a| a.0);
+    markers.agents = agents.into_iter().map(|a| a.1).collect();
+    return Ok(AsciiMap {
+        field: CustomField::new(seed, (width, y), obstacles),
+        markers,
+    });
+}
@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+/// A cell blocked during `[from, to)`. When `period` is set the window repeats every
+/// `period` ticks, `from` and `to` being offsets inside the period.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Closure {
+    pub cell: (usize, usize),
+    pub from: usize,
+    pub to: usize,
+    pub period: Option<usize>,
+}
+
+impl Closure {
+    pub fn is_active(&self, time: usize) -> bool {
+        let t = match self.period {
+            Some(p) if p > 0 => time % p,
+            _ => time,
+        };
+        return t >= self.from && t < self.to;
+    }
+}
+
+/// Time-windowed static obstacles, indexed by cell
+pub struct Closures {
+    cells: HashMap<(usize, usize), Vec<Closure>>,
+}
+
+impl Closures {
+    pub fn new(closures: Vec<Closure>) -> Self {
+        let mut cells: HashMap<(usize, usize), Vec<Closure>> = HashMap::new();
+        for c in closures {
+            cells.entry(c.cell).or_default().push(c);
+        }
+        return Closures { cells };
+    }
+
+    pub fn is_closed(&self, pos: (usize, usize), time: usize) -> bool {
+        return match self.cells.get(&pos) {
+            Some(windows) => windows.iter().any(|c| c.is_active(time)),
+            None => false,
+        };
+    }
+}
@@ -0,0 +1,138 @@
+//! Static reachability analysis
+//!
+//! Cells are adjacent when the field's neighbour iterator links them, so the
+//! analysis follows the same connectivity and corner cutting rules as the search.
+//! Time dependent elements (agents, closures) are ignored.
+
+use std::collections::VecDeque;
+
+use crate::field::field::{Field, InstanceField};
+
+const NO_LABEL: u32 = u32::MAX;
+/// Depth first search frame: cell, parent, neighbours still to visit
+type Frame = (usize, usize, Vec<(usize, usize)>);
+
+pub struct Components {
+    width: usize,
+    labels: Vec<u32>,
+    sizes: Vec<usize>,
+}
+
+impl Components {
+    /// Labels every free cell with the index of its connected component
+    pub fn new(field: &InstanceField) -> Self {
+        let width = field.width();
+        let mut labels = vec![NO_LABEL; field.width() * field.height()];
+        let mut sizes = Vec::new();
+        let mut queue = VecDeque::new();
+
+        for y in 0..field.height() {
+            for x in 0..width {
+                if labels[y * width + x] != NO_LABEL || field.is_obstacle(x, y) {
+                    continue;
+                }
+
+                let label = sizes.len() as u32;
+                let mut size = 0;
+                labels[y * width + x] = label;
+                queue.push_back((x, y));
+                while let Some(cur) = queue.pop_front() {
+                    size += 1;
+                    for adj in field.iter_neighbors(cur.0, cur.1) {
+                        if labels[adj.1 * width + adj.0] == NO_LABEL {
+                            labels[adj.1 * width + adj.0] = label;
+                            queue.push_back(adj);
+                        }
+                    }
+                }
+                sizes.push(size);
+            }
+        }
+
+        return Components { width, labels, sizes };
+    }
+
+    pub fn label(&self, x: usize, y: usize) -> Option<usize> {
+        let l = self.labels[y * self.width + x];
+        return if l == NO_LABEL { None } else { Some(l as usize) };
+    }
+
+    pub fn connected(&self, a: (usize, usize), b: (usize, usize)) -> bool {
+        let la = self.label(a.0, a.1);
+        return la.is_some() && la == self.label(b.0, b.1);
+    }
+
+    pub fn count(&self) -> usize {
+        return self.sizes.len();
+    }
+
+    pub fn size(&self, label: usize) -> usize {
+        return self.sizes[label];
+    }
+
+    pub fn largest(&self) -> Option<usize> {
+        return (0..self.sizes.len()).max_by_key(|l| (self.sizes[*l], usize::MAX - *l));
+    }
+}
+
+/// Cells whose removal disconnects their component (Tarjan's articulation points).
+/// The depth first search is iterative so that big fields do not overflow the stack.
+pub fn articulation_cells(field: &InstanceField) -> Vec<(usize, usize)> {
+    let width = field.width();
+    let mut disc = vec![0usize; field.width() * field.height()];
+    let mut low = vec![0usize; field.width() * field.height()];
+    let mut is_cut = vec![false; field.width() * field.height()];
+    let mut counter = 0;
+
+    for y in 0..field.height() {
+        for x in 0..width {
+            if disc[y * width + x] != 0 || field.is_obstacle(x, y) {
+                continue;
+            }
+
+            let root = y * width + x;
+            let mut root_children = 0;
+            counter += 1;
+            disc[root] = counter;
+            low[root] = counter;
+            let mut stack: Vec<Frame> = vec![(root, usize::MAX, field.iter_neighbors(x, y).collect())];
+
+            while let Some((cur, parent, pending)) = stack.last_mut() {
+                let (cur, parent) = (*cur, *parent);
+                if let Some(adj) = pending.pop() {
+                    let a = adj.1 * width + adj.0;
+                    if a == cur {
+                        continue;
+                    }
+                    if disc[a] == 0 {
+                        counter += 1;
+                        disc[a] = counter;
+                        low[a] = counter;
+                        if cur == root {
+                            root_children += 1;
+                        }
+                        stack.push((a, cur, field.iter_neighbors(adj.0, adj.1).collect()));
+                    } else if a != parent {
+                        low[cur] = low[cur].min(disc[a]);
+                    }
+                } else {
+                    stack.pop();
+                    if parent != usize::MAX {
+                        low[parent] = low[parent].min(low[cur]);
+                        if parent != root && low[cur] >= disc[parent] {
+                            is_cut[parent] = true;
+                        }
+                    }
+                }
+            }
+            if root_children > 1 {
+                is_cut[root] = true;
+            }
+        }
+    }
+
+    return (0..is_cut.len())
+        .filter(|i| is_cut[*i])
+        .map(|i| (i % width, i / width))
+        .collect();
+}
@@ -0,0 +1,87 @@
+//! Base field with explicitly added and removed obstacles
+//!
+//! Removing a cell wins over adding it. The base keeps providing the traversal costs
+//! and the random picks.
+
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use crate::field::field::{Field, FieldKind, InstanceField};
+
+pub struct CompositeField {
+    base: Box<InstanceField>,
+    added: HashSet<(usize, usize)>,
+    removed: HashSet<(usize, usize)>,
+    obstacles: usize,
+}
+
+impl CompositeField {
+    /// The motion model and the footprint of `base` are kept by the composite field
+    pub fn build(base: InstanceField, added: Vec<(usize, usize)>, removed: Vec<(usize, usize)>) -> InstanceField {
+        let (connectivity, corner_cutting, wrap, footprint) = (base.connectivity(), base.corner_cutting(), base.wraps(), base.footprint());
+        let mut composite = CompositeField {
+            base: Box::new(base),
+            added: added.into_iter().collect(),
+            removed: removed.into_iter().collect(),
+            obstacles: 0,
+        };
+        composite.obstacles = (0..composite.height())
+            .map(|y| (0..composite.width()).filter(|x| composite.is_obstacle(*x, y)).count())
+            .sum();
+
+        let mut field = InstanceField::new(FieldKind::CompositeField(composite));
+        field.set_connectivity(connectivity);
+        field.set_corner_cutting(corner_cutting);
+        field.set_wrap(wrap).expect("Composite fields have the size of their base");
+        field.set_footprint(footprint);
+        return field;
+    }
+
+    pub fn base(&self) -> &InstanceField {
+        return &self.base;
+    }
+}
+
+impl Field for CompositeField {
+    fn is_obstacle(&self, x: usize, y: usize) -> bool {
+        if self.removed.contains(&(x, y)) {
+            return false;
+        }
+        return self.added.contains(&(x, y)) || self.base.is_obstacle(x, y);
+    }
+
+    fn obstacles(&self) -> usize {
+        return self.obstacles;
+    }
+
+    fn width(&self) -> usize {
+        return self.base.width();
+    }
+
+    fn height(&self) -> usize {
+        return self.base.height();
+    }
+
+    fn cost(&self, x: usize, y: usize) -> f64 {
+        return self.base.cost(x, y);
+    }
+
+    fn rng(&mut self) -> &mut Xoshiro256PlusPlus {
+        return self.base.rng();
+    }
+}
+
+impl Display for CompositeField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut s = String::from("");
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                s.push(if self.is_obstacle(x, y) { '#' } else { '.' });
+            }
+            s.push('\n');
+        }
+        write!(f, "Field({}x{}), COMPOSITE: +{} -{}\n{}", self.width(), self.height(), self.added.len(), self.removed.len(), s)
+    }
+}
@@ -0,0 +1,107 @@
+/// Moves allowed from a cell, waiting in place included.
+/// Offsets are listed in row major order so neighbours are visited top-left to bottom-right.
+const FOUR: [(i64, i64); 5] = [
+    (0, -1),
+    (-1, 0), (0, 0), (1, 0),
+    (0, 1),
+];
+
+const EIGHT: [(i64, i64); 9] = [
+    (-1, -1), (0, -1), (1, -1),
+    (-1, 0), (0, 0), (1, 0),
+    (-1, 1), (0, 1), (1, 1),
+];
+
+const SIXTEEN: [(i64, i64); 17] = [
+    (-1, -2), (1, -2),
+    (-2, -1), (-1, -1), (0, -1), (1, -1), (2, -1),
+    (-1, 0), (0, 0), (1, 0),
+    (-2, 1), (-1, 1), (0, 1), (1, 1), (2, 1),
+    (-1, 2), (1, 2),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    Four,
+    Eight,
+    Sixteen,
+}
+
+impl Connectivity {
+    pub fn from_neighbors(neighbors: usize) -> Result<Self, String> {
+        return match neighbors {
+            4 => Ok(Connectivity::Four),
+            8 => Ok(Connectivity::Eight),
+            16 => Ok(Connectivity::Sixteen),
+            n => Err(format!("Unsupported connectivity {}, expected 4, 8 or 16", n)),
+        };
+    }
+
+    pub fn neighbors(&self) -> usize {
+        return match self {
+            Connectivity::Four => 4,
+            Connectivity::Eight => 8,
+            Connectivity::Sixteen => 16,
+        };
+    }
+
+    pub fn moves(&self) -> &'static [(i64, i64)] {
+        return match self {
+            Connectivity::Four => &FOUR,
+            Connectivity::Eight => &EIGHT,
+            Connectivity::Sixteen => &SIXTEEN,
+        };
+    }
+}
+
+/// Rule deciding whether a diagonal move may squeeze past blocked cells.
+/// The cells checked are the ones the move sweeps across: for a unit diagonal the two
+/// orthogonally adjacent cells, for a knight move the two cells it crosses halfway.
+/// Knight moves go through both of them, so they are never allowed past a blocked one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CornerCutting {
+    Allow,
+    ForbidIfEither,
+    ForbidIfBoth,
+}
+
+impl CornerCutting {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        return match name {
+            "allow" => Ok(CornerCutting::Allow),
+            "forbid_if_either" => Ok(CornerCutting::ForbidIfEither),
+            "forbid_if_both" => Ok(CornerCutting::ForbidIfBoth),
+            n => Err(format!("Unsupported corner cutting policy '{}', expected allow, forbid_if_either or forbid_if_both", n)),
+        };
+    }
+
+    pub fn name(&self) -> &'static str {
+        return match self {
+            CornerCutting::Allow => "allow",
+            CornerCutting::ForbidIfEither => "forbid_if_either",
+            CornerCutting::ForbidIfBoth => "forbid_if_both",
+        };
+    }
+
+    /// Cells swept by a move of `(dx, dy)` from the origin, None for moves that sweep nothing
+    pub fn swept_cells(dx: i64, dy: i64) -> Option<((i64, i64), (i64, i64))> {
+        if dx == 0 || dy == 0 {
+            return None;
+        }
+        if dy.abs() == 2 {
+            return Some(((0, dy / 2), (dx, dy / 2)));
+        }
+        if dx.abs() == 2 {
+            return Some(((dx / 2, 0), (dx / 2, dy)));
+        }
+        return Some(((dx, 0), (0, dy)));
+    }
+
+    pub fn allows(&self, first_blocked: bool, second_blocked: bool) -> bool {
+        return match self {
+            CornerCutting::Allow => true,
+            CornerCutting::ForbidIfEither => !first_blocked && !second_blocked,
+            CornerCutting::ForbidIfBoth => !first_blocked || !second_blocked,
+        };
+    }
+}
@@ -0,0 +1,151 @@
+//! Rooms and corridors generator
+//!
+//! Up to `rooms` rectangles are placed at random without touching each other or the
+//! border, then every room is joined to the previously placed one by an L shaped
+//! corridor `corridor_width` cells wide. Corridors reach the rooms through doorways
+//! as wide as the corridor, which are the bottlenecks of the instance.
+
+use std::fmt::{Display, Formatter};
+
+use rand_xoshiro::rand_core::{RngCore, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use crate::field::field::{Field, FieldKind, InstanceField};
+
+/// Placement attempts per requested room before giving up
+const ATTEMPTS: usize = 50;
+
+#[derive(Debug, Clone, Copy)]
+pub struct DungeonParams {
+    pub rooms: usize,
+    /// Inclusive range of the room widths
+    pub room_width: (usize, usize),
+    /// Inclusive range of the room heights
+    pub room_height: (usize, usize),
+    pub corridor_width: usize,
+}
+
+pub struct DungeonField {
+    width: usize,
+    height: usize,
+    obstacles: usize,
+    rng: Xoshiro256PlusPlus,
+    blocked: Vec<bool>,
+    rooms: Vec<(usize, usize, usize, usize)>,
+    params: DungeonParams,
+}
+
+fn rnd_range(rng: &mut Xoshiro256PlusPlus, range: (usize, usize)) -> usize {
+    let (lo, hi) = (range.0.min(range.1), range.0.max(range.1));
+    return lo + (rng.next_u64() % (hi - lo + 1) as u64) as usize;
+}
+
+impl DungeonField {
+    pub fn generate(seed: u64, size: (usize, usize), params: DungeonParams) -> InstanceField {
+        let (width, height) = size;
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+        let mut blocked = vec![true; width * height];
+
+        // (x, y, w, h) of the placed rooms
+        let mut rooms: Vec<(usize, usize, usize, usize)> = Vec::with_capacity(params.rooms);
+        for _ in 0..params.rooms * ATTEMPTS {
+            if rooms.len() >= params.rooms {
+                break;
+            }
+            let w = rnd_range(&mut rng, params.room_width).max(1);
+            let h = rnd_range(&mut rng, params.room_height).max(1);
+            if w + 2 > width || h + 2 > height {
+                continue;
+            }
+            let x = 1 + (rng.next_u64() % (width - w - 1) as u64) as usize;
+            let y = 1 + (rng.next_u64() % (height - h - 1) as u64) as usize;
+            // keep at least one wall between rooms
+            let overlaps = rooms.iter().any(|r| x <= r.0 + r.2 && r.0 <= x + w && y <= r.1 + r.3 && r.1 <= y + h);
+            if !overlaps {
+                rooms.push((x, y, w, h));
+            }
+        }
+
+        let mut carve = |x0: usize, y0: usize, x1: usize, y1: usize| {
+            for y in y0.min(y1)..=y0.max(y1) {
+                for x in x0.min(x1)..=x0.max(x1) {
+                    if x > 0 && y > 0 && x + 1 < width && y + 1 < height {
+                        blocked[y * width + x] = false;
+                    }
+                }
+            }
+        };
+
+        for r in rooms.iter() {
+            carve(r.0, r.1, r.0 + r.2 - 1, r.1 + r.3 - 1);
+        }
+
+        let cw = params.corridor_width.max(1);
+        for i in 1..rooms.len() {
+            let (a, b) = (rooms[i - 1], rooms[i]);
+            let (ax, ay) = (a.0 + a.2 / 2, a.1 + a.3 / 2);
+            let (bx, by) = (b.0 + b.2 / 2, b.1 + b.3 / 2);
+            if rng.next_u32() % 2 == 0 {
+                carve(ax, ay, bx, ay + cw - 1);
+                carve(bx, ay, bx + cw - 1, by);
+            } else {
+                carve(ax, ay, ax + cw - 1, by);
+                carve(ax, by, bx, by + cw - 1);
+            }
+        }
+
+        return InstanceField::new(FieldKind::DungeonField(DungeonField {
+            width,
+            height,
+            obstacles: blocked.iter().filter(|b| **b).count(),
+            rng: Xoshiro256PlusPlus::seed_from_u64(seed),
+            blocked,
+            rooms,
+            params,
+        }));
+    }
+
+    pub fn params(&self) -> &DungeonParams {
+        return &self.params;
+    }
+
+    /// Placed rooms as (x, y, width, height)
+    pub fn rooms(&self) -> &Vec<(usize, usize, usize, usize)> {
+        return &self.rooms;
+    }
+}
+
+impl Field for DungeonField {
+    fn is_obstacle(&self, x: usize, y: usize) -> bool {
+        return self.blocked[y * self.width + x];
+    }
+
+    fn obstacles(&self) -> usize {
+        return self.obstacles;
+    }
+
+    fn width(&self) -> usize {
+        return self.width;
+    }
+
+    fn height(&self) -> usize {
+        return self.height;
+    }
+
+    fn rng(&mut self) -> &mut Xoshiro256PlusPlus {
+        return &mut self.rng;
+    }
+}
+
+impl Display for DungeonField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut s = String::from("");
+        for y in 0..self.height {
+            for x in 0..self.width {
+                s.push(if self.is_obstacle(x, y) { '#' } else { '.' });
+            }
+            s.push('\n');
+        }
+        write!(f, "Field({}x{}), DUNGEON: {} rooms, CORRIDOR: {}\n{}", self.width, self.height, self.rooms.len(), self.params.corridor_width, s)
+    }
+}
@@ -1,65 +1,235 @@
-use std::collections::{BTreeSet, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fmt::{Display, Formatter};
 
 use rand_xoshiro::rand_core::{RngCore, SeedableRng};
 use rand_xoshiro::Xoshiro256PlusPlus;
 
+use crate::field::connectivity::{Connectivity, CornerCutting};
+use crate::field::maze::MazeField;
+use crate::field::dungeon::DungeonField;
+use crate::field::warehouse::WarehouseField;
+use crate::field::composite::CompositeField;
 use crate::field::neighbor_iterator::NeighborIterator;
-use crate::noise::perlin::PerlinNoise;
+use crate::field::{footprint_cells, step_weight};
+use crate::noise::batch::fold_strips;
+use crate::noise::NoiseSource;
 
-pub enum InstanceField {
+pub enum FieldKind {
     RandomField(RandomField),
     CustomField(CustomField),
+    BitmapField(BitmapField),
+    MazeField(MazeField),
+    DungeonField(DungeonField),
+    WarehouseField(WarehouseField),
+    CompositeField(CompositeField),
+}
+
+/// A field together with the motion model used to move on it.
+pub struct InstanceField {
+    kind: FieldKind,
+    connectivity: Connectivity,
+    corner_cutting: CornerCutting,
+    wrap: bool,
+    /// Cells taken by the planned agent, anchored at its position
+    footprint: (usize, usize),
 }
 
 impl Field for InstanceField {
     fn is_obstacle(&self, x: usize, y: usize) -> bool {
-        return match self {
-            InstanceField::RandomField(e) => e.is_obstacle(x, y),
-            InstanceField::CustomField(e) => e.is_obstacle(x, y)
+        return match &self.kind {
+            FieldKind::RandomField(e) => e.is_obstacle(x, y),
+            FieldKind::CustomField(e) => e.is_obstacle(x, y),
+            FieldKind::BitmapField(e) => e.is_obstacle(x, y),
+            FieldKind::MazeField(e) => e.is_obstacle(x, y),
+            FieldKind::DungeonField(e) => e.is_obstacle(x, y),
+            FieldKind::WarehouseField(e) => e.is_obstacle(x, y),
+            FieldKind::CompositeField(e) => e.is_obstacle(x, y)
         };
     }
 
     fn obstacles(&self) -> usize {
-        return match self {
-            InstanceField::RandomField(e) => e.obstacles(),
-            InstanceField::CustomField(e) => e.obstacles()
+        return match &self.kind {
+            FieldKind::RandomField(e) => e.obstacles(),
+            FieldKind::CustomField(e) => e.obstacles(),
+            FieldKind::BitmapField(e) => e.obstacles(),
+            FieldKind::MazeField(e) => e.obstacles(),
+            FieldKind::DungeonField(e) => e.obstacles(),
+            FieldKind::WarehouseField(e) => e.obstacles(),
+            FieldKind::CompositeField(e) => e.obstacles()
         };
     }
 
     fn width(&self) -> usize {
-        return match self {
-            InstanceField::RandomField(e) => e.width(),
-            InstanceField::CustomField(e) => e.width()
+        return match &self.kind {
+            FieldKind::RandomField(e) => e.width(),
+            FieldKind::CustomField(e) => e.width(),
+            FieldKind::BitmapField(e) => e.width(),
+            FieldKind::MazeField(e) => e.width(),
+            FieldKind::DungeonField(e) => e.width(),
+            FieldKind::WarehouseField(e) => e.width(),
+            FieldKind::CompositeField(e) => e.width()
         };
     }
 
     fn height(&self) -> usize {
-        return match self {
-            InstanceField::RandomField(e) => e.height(),
-            InstanceField::CustomField(e) => e.height()
+        return match &self.kind {
+            FieldKind::RandomField(e) => e.height(),
+            FieldKind::CustomField(e) => e.height(),
+            FieldKind::BitmapField(e) => e.height(),
+            FieldKind::MazeField(e) => e.height(),
+            FieldKind::DungeonField(e) => e.height(),
+            FieldKind::WarehouseField(e) => e.height(),
+            FieldKind::CompositeField(e) => e.height()
+        };
+    }
+
+    fn cost(&self, x: usize, y: usize) -> f64 {
+        return match &self.kind {
+            FieldKind::RandomField(e) => e.cost(x, y),
+            FieldKind::CustomField(e) => e.cost(x, y),
+            FieldKind::BitmapField(e) => e.cost(x, y),
+            FieldKind::MazeField(e) => e.cost(x, y),
+            FieldKind::DungeonField(e) => e.cost(x, y),
+            FieldKind::WarehouseField(e) => e.cost(x, y),
+            FieldKind::CompositeField(e) => e.cost(x, y)
         };
     }
 
     fn rng(&mut self) -> &mut Xoshiro256PlusPlus {
-        return match self {
-            InstanceField::RandomField(e) => e.rng(),
-            InstanceField::CustomField(e) => e.rng()
+        return match &mut self.kind {
+            FieldKind::RandomField(e) => e.rng(),
+            FieldKind::CustomField(e) => e.rng(),
+            FieldKind::BitmapField(e) => e.rng(),
+            FieldKind::MazeField(e) => e.rng(),
+            FieldKind::DungeonField(e) => e.rng(),
+            FieldKind::WarehouseField(e) => e.rng(),
+            FieldKind::CompositeField(e) => e.rng()
         };
     }
 }
 
 impl InstanceField {
-    pub fn iter_neighbors(&self, x: usize, y: usize) -> NeighborIterator {
-        return NeighborIterator::new(self, (x, y));
+    pub fn new(kind: FieldKind) -> Self {
+        return InstanceField {
+            kind,
+            connectivity: Connectivity::Eight,
+            corner_cutting: CornerCutting::Allow,
+            wrap: false,
+            footprint: (1, 1),
+        };
+    }
+
+    pub fn kind(&self) -> &FieldKind {
+        return &self.kind;
+    }
+
+    /// Evaluates every cell once into a `BitmapField`, noise fields in parallel.
+    /// Like `BitmapField::from_field`, the motion model is reset to the defaults.
+    pub fn materialize(self) -> InstanceField {
+        return match self.kind {
+            FieldKind::RandomField(f) => f.materialize(),
+            kind => BitmapField::from_field(InstanceField::new(kind)),
+        };
+    }
+
+    pub fn iter_neighbors(&self, x: usize, y: usize) -> NeighborIterator<'_> {
+        return self.iter_neighbors_of(x, y, self.footprint);
+    }
+
+    /// Neighbours of an agent with its own footprint, rather than the planned agent's one
+    pub fn iter_neighbors_of(&self, x: usize, y: usize, footprint: (usize, usize)) -> NeighborIterator<'_> {
+        return NeighborIterator::new(self, (x, y), self.connectivity.moves(), self.corner_cutting, footprint);
+    }
+
+    /// Cost of moving from `a` to `b` (or waiting when `a == b`): the length of the move
+    /// scaled by the mean traversal cost of the two cells.
+    pub fn move_weight(&self, a: &(usize, usize), b: &(usize, usize)) -> f64 {
+        let (dx, dy) = self.delta(a, b);
+        return step_weight(dx, dy) * (self.cost(a.0, a.1) + self.cost(b.0, b.1)) / 2.0;
+    }
+
+    /// Shortest displacement from `b` to `a`, going through the borders when the field wraps around
+    pub fn delta(&self, a: &(usize, usize), b: &(usize, usize)) -> (i64, i64) {
+        let axis = |a: usize, b: usize, size: usize| {
+            let d = a as i64 - b as i64;
+            if !self.wrap {
+                return d;
+            }
+            let size = size as i64;
+            let d = d.rem_euclid(size);
+            return if 2 * d > size { d - size } else { d };
+        };
+        return (axis(a.0, b.0, self.width()), axis(a.1, b.1, self.height()));
+    }
+
+    /// Cell reached from `p` with the offset, None when it falls off the borders of a non wrapping field
+    pub fn offset(&self, p: (usize, usize), offset: (i64, i64)) -> Option<(usize, usize)> {
+        let (x, y) = (p.0 as i64 + offset.0, p.1 as i64 + offset.1);
+        if self.wrap {
+            return Some((x.rem_euclid(self.width() as i64) as usize, y.rem_euclid(self.height() as i64) as usize));
+        }
+        if x < 0 || y < 0 || !self.exists(x as usize, y as usize) {
+            return None;
+        }
+        return Some((x as usize, y as usize));
+    }
+
+    pub fn connectivity(&self) -> Connectivity {
+        return self.connectivity;
+    }
+
+    pub fn set_connectivity(&mut self, connectivity: Connectivity) {
+        self.connectivity = connectivity;
+    }
+
+    pub fn corner_cutting(&self) -> CornerCutting {
+        return self.corner_cutting;
+    }
+
+    pub fn set_corner_cutting(&mut self, corner_cutting: CornerCutting) {
+        self.corner_cutting = corner_cutting;
+    }
+
+    /// Whether moving off an edge enters the field again from the opposite one
+    pub fn wraps(&self) -> bool {
+        return self.wrap;
+    }
+
+    /// Wrapping needs at least 5 cells per axis, so that the 16 neighbourhood offsets
+    /// still land on distinct cells
+    pub fn set_wrap(&mut self, wrap: bool) -> Result<(), String> {
+        if wrap && (self.width() < 5 || self.height() < 5) {
+            return Err(format!("Grid is {}x{}, wrap-around needs at least 5x5 cells", self.width(), self.height()));
+        }
+        self.wrap = wrap;
+        return Ok(());
+    }
+
+    pub fn footprint(&self) -> (usize, usize) {
+        return self.footprint;
+    }
+
+    /// Neighbours are only the positions where the whole footprint lies on free cells
+    pub fn set_footprint(&mut self, footprint: (usize, usize)) {
+        self.footprint = (footprint.0.max(1), footprint.1.max(1));
+    }
+
+    /// Whether the footprint anchored at `(x, y)` lies on free cells of the grid
+    pub fn fits(&self, x: usize, y: usize) -> bool {
+        return footprint_cells((x, y), self.footprint).all(|(x, y)| self.exists(x, y) && !self.is_obstacle(x, y));
     }
 }
 
 impl Display for InstanceField {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        return match self {
-            InstanceField::RandomField(e) => e.fmt(f),
-            InstanceField::CustomField(e) => e.fmt(f)
+        return match &self.kind {
+            FieldKind::RandomField(e) => e.fmt(f),
+            FieldKind::CustomField(e) => e.fmt(f),
+            FieldKind::BitmapField(e) => e.fmt(f),
+            FieldKind::MazeField(e) => e.fmt(f),
+            FieldKind::DungeonField(e) => e.fmt(f),
+            FieldKind::WarehouseField(e) => e.fmt(f),
+            FieldKind::CompositeField(e) => e.fmt(f)
         };
     }
 }
@@ -70,21 +240,29 @@ pub trait Field {
     fn width(&self) -> usize;
     fn height(&self) -> usize;
     fn rng(&mut self) -> &mut Xoshiro256PlusPlus;
-    fn rnd_pick(&mut self, occupied: &HashSet<(usize, usize)>) -> Result<(usize, usize), ()> {
+    /// Traversal cost of a free cell, multiplies the cost of the moves touching it
+    fn cost(&self, _x: usize, _y: usize) -> f64 {
+        return 1.0;
+    }
+    fn rnd_pick(&mut self, occupied: &HashSet<(usize, usize)>) -> Option<(usize, usize)> {
+        return self.rnd_pick_where(occupied, |_| true);
+    }
+    /// Like `rnd_pick`, restricted to the free cells accepted by `allowed`
+    fn rnd_pick_where<P: Fn((usize, usize)) -> bool>(&mut self, occupied: &HashSet<(usize, usize)>, allowed: P) -> Option<(usize, usize)> {
         let mut x = (self.rng().next_u64() % self.width() as u64) as usize;
         let mut y = (self.rng().next_u64() % self.height() as u64) as usize;
         let mut times = 0;
-        while self.is_obstacle(x, y) || occupied.contains(&(x, y)) {
+        while self.is_obstacle(x, y) || occupied.contains(&(x, y)) || !allowed((x, y)) {
             x = (x + 1) % self.width();
             if x == 0 {
                 y = (y + 1) % self.height();
             }
             times += 1;
             if times >= self.width() * self.height() {
-                return Err(());
+                return None;
             }
         }
-        return Ok((x, y));
+        return Some((x, y));
     }
     fn exists(&self, x: usize, y: usize) -> bool {
         return x < self.width() && y < self.height();
@@ -100,14 +278,21 @@ pub struct RandomField {
     width: usize,
     height: usize,
     obstacles: usize,
+    terrain_weight: f64,
 
     rng: Xoshiro256PlusPlus,
-    field_noise: PerlinNoise,
+    field_noise: Box<dyn NoiseSource>,
 }
 
 impl RandomField {
-    pub fn new(noise: PerlinNoise, val_limit: u32, cell_limit: usize, size: (usize, usize), obstacles: usize) -> InstanceField {
-        return InstanceField::RandomField(RandomField {
+    pub fn new(noise: Box<dyn NoiseSource>, val_limit: u32, cell_limit: usize, size: (usize, usize), obstacles: usize) -> InstanceField {
+        return RandomField::with_terrain(noise, val_limit, cell_limit, size, obstacles, 0.0);
+    }
+
+    /// Free cells cost `1 + terrain_weight * h` where `h` in [0, 1] is how far the noise
+    /// value lies above the obstacle threshold.
+    pub fn with_terrain(noise: Box<dyn NoiseSource>, val_limit: u32, cell_limit: usize, size: (usize, usize), obstacles: usize, terrain_weight: f64) -> InstanceField {
+        return InstanceField::new(FieldKind::RandomField(RandomField {
             val_limit,
             cell_limit,
             width: size.0,
@@ -115,14 +300,72 @@ impl RandomField {
             rng: Xoshiro256PlusPlus::seed_from_u64(noise.get_seed()),
             field_noise: noise,
             obstacles,
+            terrain_weight,
+        }));
+    }
+}
+
+impl RandomField {
+    fn is_obstacle_value(&self, val: u32, cell: usize) -> bool {
+        return val < self.val_limit || (val == self.val_limit && cell <= self.cell_limit);
+    }
+
+    fn cost_value(&self, val: u32) -> f64 {
+        if self.terrain_weight == 0.0 {
+            return 1.0;
+        }
+        let val = val.saturating_sub(self.val_limit);
+        return 1.0 + self.terrain_weight * val as f64 / (u32::MAX - self.val_limit) as f64;
+    }
+
+    /// Same as `BitmapField::from_field`, evaluating the noise in strips spread across threads
+    fn materialize(mut self) -> InstanceField {
+        let width = self.width;
+        let terrain = self.terrain_weight != 0.0;
+        // obstacle cells and, with a terrain, the cost of every cell of the thread's rows
+        let parts = fold_strips(self.field_noise.as_ref(), (self.width, self.height), || (Vec::new(), Vec::new()), |(obstacles, costs): &mut (Vec<usize>, Vec<f64>), tile, values| {
+            for (i, val) in values.iter().enumerate() {
+                let cell = tile.y * width + i;
+                let blocked = self.is_obstacle_value(*val, cell);
+                if blocked {
+                    obstacles.push(cell);
+                }
+                if terrain {
+                    costs.push(if blocked { 1.0 } else { self.cost_value(*val) });
+                }
+            }
         });
+
+        let mut bits = vec![0u64; (self.width * self.height).div_ceil(64)];
+        let mut costs = Vec::with_capacity(if terrain { self.width * self.height } else { 0 });
+        for (obstacles, part_costs) in parts {
+            for cell in obstacles {
+                bits[cell / 64] |= 1 << (cell % 64);
+            }
+            costs.extend(part_costs);
+        }
+
+        return InstanceField::new(FieldKind::BitmapField(BitmapField {
+            width: self.width,
+            height: self.height,
+            obstacles: self.obstacles,
+            rng: self.rng().clone(),
+            bits,
+            costs: if costs.iter().all(|c| *c == 1.0) { None } else { Some(costs) },
+        }));
     }
 }
 
 impl Field for RandomField {
     fn is_obstacle(&self, x: usize, y: usize) -> bool {
-        let val = self.field_noise.get_noise_u32(x, y);
-        return val < self.val_limit || (val == self.val_limit && y * self.width + x <= self.cell_limit);
+        return self.is_obstacle_value(self.field_noise.get_noise_u32(x, y), y * self.width + x);
+    }
+
+    fn cost(&self, x: usize, y: usize) -> f64 {
+        if self.terrain_weight == 0.0 {
+            return 1.0;
+        }
+        return self.cost_value(self.field_noise.get_noise_u32(x, y));
     }
 
     fn obstacles(&self) -> usize {
@@ -151,7 +394,7 @@ impl Display for RandomField {
             }
             s.push('\n');
         }
-        write!(f, "Field({}x{}), VAL_LIMIT: {}, CELL_LIMIT: {}\n{}", self.width, self.height, self.val_limit, self.cell_limit, s)
+        write!(f, "Field({}x{}), NOISE: {}, VAL_LIMIT: {}, CELL_LIMIT: {}\n{}", self.width, self.height, self.field_noise.kind().name(), self.val_limit, self.cell_limit, s)
     }
 }
 
@@ -160,16 +403,23 @@ pub struct CustomField {
     height: usize,
     rng: Xoshiro256PlusPlus,
     obstacles: HashSet<(usize, usize)>,
+    costs: HashMap<(usize, usize), f64>,
 }
 
 impl CustomField {
     pub fn new(seed: u64, size: (usize, usize), obstacles: Vec<(usize, usize)>) -> InstanceField {
-        return InstanceField::CustomField(CustomField {
+        return CustomField::with_costs(seed, size, obstacles, vec![]);
+    }
+
+    /// Cells not listed in `costs` cost 1
+    pub fn with_costs(seed: u64, size: (usize, usize), obstacles: Vec<(usize, usize)>, costs: Vec<(usize, usize, f64)>) -> InstanceField {
+        return InstanceField::new(FieldKind::CustomField(CustomField {
             width: size.0,
             height: size.1,
             rng: Xoshiro256PlusPlus::seed_from_u64(seed),
             obstacles: HashSet::from_iter(obstacles),
-        });
+            costs: costs.into_iter().map(|(x, y, c)| ((x, y), c)).collect(),
+        }));
     }
 }
 
@@ -178,6 +428,10 @@ impl Field for CustomField {
         return self.obstacles.contains(&(x, y));
     }
 
+    fn cost(&self, x: usize, y: usize) -> f64 {
+        return self.costs.get(&(x, y)).cloned().unwrap_or(1.0);
+    }
+
     fn obstacles(&self) -> usize {
         return self.obstacles.len();
     }
@@ -206,4 +460,90 @@ impl Display for CustomField {
         }
         write!(f, "Field({}x{})\n{}", self.width, self.height, s)
     }
-}
\ No newline at end of file
+}
+
+/// Dense field backed by a packed bitset (one bit per cell, row major).
+///
+/// It is meant to be materialized once from a procedural field (e.g. a `RandomField`)
+/// so that obstacle queries become a memory lookup instead of a noise evaluation.
+pub struct BitmapField {
+    width: usize,
+    height: usize,
+    obstacles: usize,
+    rng: Xoshiro256PlusPlus,
+    bits: Vec<u64>,
+    costs: Option<Vec<f64>>,
+}
+
+impl BitmapField {
+    /// Evaluates every cell of `field` once and stores the result.
+    /// The rng state is taken over from the source field, so random picks stay reproducible.
+    pub fn from_field<F: Field>(mut field: F) -> InstanceField {
+        let (width, height) = (field.width(), field.height());
+        let mut bits = vec![0u64; (width * height).div_ceil(64)];
+        let mut costs = vec![1.0; width * height];
+        for y in 0..height {
+            for x in 0..width {
+                let idx = y * width + x;
+                if field.is_obstacle(x, y) {
+                    bits[idx / 64] |= 1 << (idx % 64);
+                } else {
+                    costs[idx] = field.cost(x, y);
+                }
+            }
+        }
+
+        return InstanceField::new(FieldKind::BitmapField(BitmapField {
+            width,
+            height,
+            obstacles: field.obstacles(),
+            rng: field.rng().clone(),
+            bits,
+            // uniform fields do not need the cost table
+            costs: if costs.iter().all(|c| *c == 1.0) { None } else { Some(costs) },
+        }));
+    }
+}
+
+impl Field for BitmapField {
+    fn is_obstacle(&self, x: usize, y: usize) -> bool {
+        let idx = y * self.width + x;
+        return self.bits[idx / 64] & (1 << (idx % 64)) != 0;
+    }
+
+    fn cost(&self, x: usize, y: usize) -> f64 {
+        return match self.costs.as_ref() {
+            Some(costs) => costs[y * self.width + x],
+            None => 1.0,
+        };
+    }
+
+    fn obstacles(&self) -> usize {
+        return self.obstacles;
+    }
+
+    fn width(&self) -> usize {
+        return self.width;
+    }
+
+    fn height(&self) -> usize {
+        return self.height;
+    }
+
+    fn rng(&mut self) -> &mut Xoshiro256PlusPlus {
+        return &mut self.rng;
+    }
+}
+
+impl Display for BitmapField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut s = String::from("");
+        for y in 0..self.height {
+            for x in 0..self.width {
+                s.push(if self.is_obstacle(x, y) { '#' } else { '.' });
+            }
+            s.push('\n');
+        }
+        write!(f, "Field({}x{}), BITMAP\n{}", self.width, self.height, s)
+    }
+}
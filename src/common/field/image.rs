@@ -0,0 +1,165 @@
+//! Grayscale images of fields and noise
+//!
+//! Reads PGM (P2 and P5) and PNG images, whatever their depth and colour type, as 8 bit
+//! luma. Obstacles are the pixels darker than a threshold, so maps can be drawn in
+//! black on white. Fields are written with obstacles in black and free cells in white,
+//! darkened by their traversal cost.
+
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::field::field::{CustomField, Field, InstanceField};
+use crate::noise::NoiseSource;
+
+pub const DEFAULT_THRESHOLD: u8 = 128;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+pub struct GrayImage {
+    pub width: usize,
+    pub height: usize,
+    /// Row major luma, 0 is black
+    pub pixels: Vec<u8>,
+}
+
+impl GrayImage {
+    pub fn from_field(field: &InstanceField) -> Self {
+        let mut pixels = Vec::with_capacity(field.width() * field.height());
+        for y in 0..field.height() {
+            for x in 0..field.width() {
+                pixels.push(if field.is_obstacle(x, y) { 0 } else { (255.0 / field.cost(x, y).max(1.0)).round() as u8 });
+            }
+        }
+        return GrayImage { width: field.width(), height: field.height(), pixels };
+    }
+
+    /// Normalized noise values, the darkest cells being the first to become obstacles
+    pub fn from_noise(noise: &dyn NoiseSource, size: (usize, usize)) -> Self {
+        let mut pixels = Vec::with_capacity(size.0 * size.1);
+        for y in 0..size.1 {
+            for x in 0..size.0 {
+                pixels.push((noise.gen_normalized(x, y).clamp(0.0, 1.0) * 255.0).round() as u8);
+            }
+        }
+        return GrayImage { width: size.0, height: size.1, pixels };
+    }
+
+    pub fn to_field(&self, threshold: u8, seed: u64) -> InstanceField {
+        let obstacles = (0..self.pixels.len())
+            .filter(|i| self.pixels[*i] < threshold)
+            .map(|i| (i % self.width, i / self.width))
+            .collect();
+        return CustomField::new(seed, (self.width, self.height), obstacles);
+    }
+
+    pub fn write_pgm<W: Write>(&self, out: &mut W) -> std::io::Result<()> {
+        write!(out, "P5\n{} {}\n255\n", self.width, self.height)?;
+        return out.write_all(&self.pixels);
+    }
+
+    pub fn write_png<W: Write>(&self, out: &mut W) -> Result<(), String> {
+        let mut encoder = png::Encoder::new(out, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|e| format!("Cannot write png header: {}", e))?;
+        return writer.write_image_data(&self.pixels).map_err(|e| format!("Cannot write png data: {}", e));
+    }
+
+    /// PNG when the path ends in `.png`, PGM otherwise
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| format!("Cannot create image '{}': {}", path, e))?;
+        let mut out = BufWriter::new(file);
+        if path.to_lowercase().ends_with(".png") {
+            return self.write_png(&mut out);
+        }
+        return self.write_pgm(&mut out).map_err(|e| format!("Cannot write image '{}': {}", path, e));
+    }
+}
+
+pub fn parse_pgm(bytes: &[u8]) -> Result<GrayImage, String> {
+    let mut pos = 0;
+    // whitespace separated header token, skipping comments
+    let token = |pos: &mut usize| -> Result<String, String> {
+        loop {
+            while *pos < bytes.len() && bytes[*pos].is_ascii_whitespace() {
+                *pos += 1;
+            }
+            if *pos < bytes.len() && bytes[*pos] == b'#' {
+                while *pos < bytes.len() && bytes[*pos] != b'\n' {
+                    *pos += 1;
+                }
+                continue;
+            }
+            break;
+        }
+        let start = *pos;
+        while *pos < bytes.len() && !bytes[*pos].is_ascii_whitespace() {
+            *pos += 1;
+        }
+        if start == *pos {
+            return Err("Truncated pgm image".to_string());
+        }
+        return Ok(String::from_utf8_lossy(&bytes[start..*pos]).to_string());
+    };
+
+    let magic = token(&mut pos)?;
+    if magic != "P2" && magic != "P5" {
+        return Err(format!("Unsupported pgm format '{}', expected P2 or P5", magic));
+    }
+    let number = |pos: &mut usize, what: &str| -> Result<usize, String> {
+        let t = token(pos)?;
+        return t.parse::<usize>().map_err(|_| format!("Invalid pgm {} '{}'", what, t));
+    };
+    let width = number(&mut pos, "width")?;
+    let height = number(&mut pos, "height")?;
+    let max = number(&mut pos, "maximum value")?;
+    if max == 0 || max > 65535 {
+        return Err(format!("Invalid pgm maximum value {}", max));
+    }
+
+    let scale = |v: usize| (v.min(max) * 255 / max) as u8;
+    let mut pixels = Vec::with_capacity(width * height);
+    if magic == "P2" {
+        for _ in 0..width * height {
+            let v = number(&mut pos, "pixel")?;
+            pixels.push(scale(v));
+        }
+    } else {
+        // a single whitespace separates the header from the raster
+        pos += 1;
+        let depth = if max > 255 { 2 } else { 1 };
+        let raster = bytes.get(pos..pos + width * height * depth).ok_or("Truncated pgm raster".to_string())?;
+        for p in raster.chunks(depth) {
+            let v = if depth == 2 { (p[0] as usize) << 8 | p[1] as usize } else { p[0] as usize };
+            pixels.push(scale(v));
+        }
+    }
+    return Ok(GrayImage { width, height, pixels });
+}
+
+pub fn parse_png(bytes: &[u8]) -> Result<GrayImage, String> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|e| format!("Cannot read png: {}", e))?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).map_err(|e| format!("Cannot decode png: {}", e))?;
+
+    let channels = info.color_type.samples();
+    let pixels = buf[..info.buffer_size()].chunks(channels)
+        .map(|p| match info.color_type {
+            png::ColorType::Rgb | png::ColorType::Rgba => ((p[0] as u32 * 299 + p[1] as u32 * 587 + p[2] as u32 * 114) / 1000) as u8,
+            _ => p[0],
+        })
+        .collect();
+    return Ok(GrayImage { width: info.width as usize, height: info.height as usize, pixels });
+}
+
+/// Reads a PNG or PGM image, recognised by its signature
+pub fn load_image(path: &str) -> Result<GrayImage, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Cannot read image '{}': {}", path, e))?;
+    if bytes.starts_with(&PNG_SIGNATURE) {
+        return parse_png(&bytes);
+    }
+    return parse_pgm(&bytes);
+}
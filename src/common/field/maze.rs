@@ -0,0 +1,230 @@
+//! Maze generator
+//!
+//! The grid is split in logical cells `corridor_width` wide separated by one cell thick
+//! walls, the outer border being a wall too. A spanning tree of the logical cells is
+//! carved with the selected algorithm, then dead ends are opened with probability
+//! `braid` to create loops. The same seed always produces the same maze.
+
+use std::fmt::{Display, Formatter};
+
+use rand_xoshiro::rand_core::{RngCore, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use crate::field::field::{Field, FieldKind, InstanceField};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MazeAlgorithm {
+    Backtracker,
+    Prim,
+}
+
+impl MazeAlgorithm {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        return match name {
+            "backtracker" => Ok(MazeAlgorithm::Backtracker),
+            "prim" => Ok(MazeAlgorithm::Prim),
+            n => Err(format!("Unsupported maze algorithm '{}', expected backtracker or prim", n)),
+        };
+    }
+
+    pub fn name(&self) -> &'static str {
+        return match self {
+            MazeAlgorithm::Backtracker => "backtracker",
+            MazeAlgorithm::Prim => "prim",
+        };
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MazeParams {
+    pub algorithm: MazeAlgorithm,
+    pub corridor_width: usize,
+    /// Probability of removing a wall from every dead end, in [0, 1]
+    pub braid: f64,
+}
+
+pub struct MazeField {
+    width: usize,
+    height: usize,
+    obstacles: usize,
+    rng: Xoshiro256PlusPlus,
+    blocked: Vec<bool>,
+    params: MazeParams,
+}
+
+/// Carving state over the logical cells
+struct Carver {
+    cols: usize,
+    rows: usize,
+    /// open passages towards the east and the south neighbour of every logical cell
+    east: Vec<bool>,
+    south: Vec<bool>,
+}
+
+impl Carver {
+    fn neighbors(&self, c: usize) -> Vec<usize> {
+        let (x, y) = (c % self.cols, c / self.cols);
+        let mut n = Vec::with_capacity(4);
+        if y > 0 { n.push(c - self.cols); }
+        if x > 0 { n.push(c - 1); }
+        if x + 1 < self.cols { n.push(c + 1); }
+        if y + 1 < self.rows { n.push(c + self.cols); }
+        return n;
+    }
+
+    fn is_open(&self, a: usize, b: usize) -> bool {
+        let (a, b) = (a.min(b), a.max(b));
+        return if b == a + 1 { self.east[a] } else { self.south[a] };
+    }
+
+    fn open(&mut self, a: usize, b: usize) {
+        let (a, b) = (a.min(b), a.max(b));
+        if b == a + 1 { self.east[a] = true; } else { self.south[a] = true; }
+    }
+
+    fn backtracker(&mut self, rng: &mut Xoshiro256PlusPlus) {
+        let mut visited = vec![false; self.cols * self.rows];
+        let mut stack = vec![0];
+        visited[0] = true;
+        while let Some(cur) = stack.last().cloned() {
+            let candidates = self.neighbors(cur).into_iter().filter(|n| !visited[*n]).collect::<Vec<usize>>();
+            if candidates.is_empty() {
+                stack.pop();
+                continue;
+            }
+            let next = candidates[rng.next_u64() as usize % candidates.len()];
+            self.open(cur, next);
+            visited[next] = true;
+            stack.push(next);
+        }
+    }
+
+    fn prim(&mut self, rng: &mut Xoshiro256PlusPlus) {
+        let mut visited = vec![false; self.cols * self.rows];
+        let mut frontier: Vec<(usize, usize)> = self.neighbors(0).into_iter().map(|n| (0, n)).collect();
+        visited[0] = true;
+        while !frontier.is_empty() {
+            let (from, to) = frontier.swap_remove(rng.next_u64() as usize % frontier.len());
+            if visited[to] {
+                continue;
+            }
+            self.open(from, to);
+            visited[to] = true;
+            for n in self.neighbors(to) {
+                if !visited[n] {
+                    frontier.push((to, n));
+                }
+            }
+        }
+    }
+
+    fn braid(&mut self, rng: &mut Xoshiro256PlusPlus, probability: f64) {
+        for c in 0..self.cols * self.rows {
+            let neighbors = self.neighbors(c);
+            if neighbors.iter().filter(|n| self.is_open(c, **n)).count() != 1 {
+                continue;
+            }
+            if (rng.next_u32() as f64 / (u32::MAX as f64)) >= probability {
+                continue;
+            }
+            let closed = neighbors.into_iter().filter(|n| !self.is_open(c, *n)).collect::<Vec<usize>>();
+            if !closed.is_empty() {
+                let n = closed[rng.next_u64() as usize % closed.len()];
+                self.open(c, n);
+            }
+        }
+    }
+}
+
+impl MazeField {
+    pub fn generate(seed: u64, size: (usize, usize), params: MazeParams) -> InstanceField {
+        let (width, height) = size;
+        let w = params.corridor_width.max(1);
+        let mut carver = Carver {
+            cols: (width.saturating_sub(1) / (w + 1)).max(1),
+            rows: (height.saturating_sub(1) / (w + 1)).max(1),
+            east: vec![],
+            south: vec![],
+        };
+        carver.east = vec![false; carver.cols * carver.rows];
+        carver.south = vec![false; carver.cols * carver.rows];
+
+        let mut carve_rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+        match params.algorithm {
+            MazeAlgorithm::Backtracker => carver.backtracker(&mut carve_rng),
+            MazeAlgorithm::Prim => carver.prim(&mut carve_rng),
+        }
+        carver.braid(&mut carve_rng, params.braid);
+
+        let mut blocked = vec![true; width * height];
+        let mut free = |x: usize, y: usize| {
+            if x < width && y < height {
+                blocked[y * width + x] = false;
+            }
+        };
+        for c in 0..carver.cols * carver.rows {
+            let (ox, oy) = (1 + (c % carver.cols) * (w + 1), 1 + (c / carver.cols) * (w + 1));
+            for dy in 0..w {
+                for dx in 0..w {
+                    free(ox + dx, oy + dy);
+                }
+                if carver.east[c] {
+                    free(ox + w, oy + dy);
+                }
+            }
+            if carver.south[c] {
+                for dx in 0..w {
+                    free(ox + dx, oy + w);
+                }
+            }
+        }
+
+        return InstanceField::new(FieldKind::MazeField(MazeField {
+            width,
+            height,
+            obstacles: blocked.iter().filter(|b| **b).count(),
+            rng: Xoshiro256PlusPlus::seed_from_u64(seed),
+            blocked,
+            params,
+        }));
+    }
+
+    pub fn params(&self) -> &MazeParams {
+        return &self.params;
+    }
+}
+
+impl Field for MazeField {
+    fn is_obstacle(&self, x: usize, y: usize) -> bool {
+        return self.blocked[y * self.width + x];
+    }
+
+    fn obstacles(&self) -> usize {
+        return self.obstacles;
+    }
+
+    fn width(&self) -> usize {
+        return self.width;
+    }
+
+    fn height(&self) -> usize {
+        return self.height;
+    }
+
+    fn rng(&mut self) -> &mut Xoshiro256PlusPlus {
+        return &mut self.rng;
+    }
+}
+
+impl Display for MazeField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut s = String::from("");
+        for y in 0..self.height {
+            for x in 0..self.width {
+                s.push(if self.is_obstacle(x, y) { '#' } else { '.' });
+            }
+            s.push('\n');
+        }
+        write!(f, "Field({}x{}), MAZE: {}, CORRIDOR: {}, BRAID: {}\n{}", self.width, self.height, self.params.algorithm.name(), self.params.corridor_width, self.params.braid, s)
+    }
+}
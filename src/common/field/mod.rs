@@ -1,116 +1,519 @@
 use std::cmp::{max, min};
 
+#[cfg(feature = "diagonal_distance")]
+use crate::field::connectivity::Connectivity;
+use crate::field::field::InstanceField;
+
 pub mod field;
 pub mod visited_node;
 pub mod open_node;
+pub mod movingai;
+pub mod connectivity;
+pub mod closure;
+pub mod components;
+pub mod maze;
+pub mod dungeon;
+pub mod warehouse;
+pub mod composite;
+pub mod ascii;
+pub mod image;
 mod neighbor_iterator;
 
 pub fn weight(a: &(usize, usize), b: &(usize, usize)) -> f64 {
     // if a == b{
     //     return 0.0;
     // }
-    return if (a.0 as i64 - b.0 as i64) == 0 || (a.1 as i64 - b.1 as i64) == 0 { 1.0 } else { f64::sqrt(2.0) };
+    return step_weight(a.0 as i64 - b.0 as i64, a.1 as i64 - b.1 as i64);
+}
+
+/// Cells covered by a `(width, height)` footprint anchored at its top left cell, footprints
+/// never wrap around the borders
+pub fn footprint_cells(anchor: (usize, usize), footprint: (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
+    return (0..footprint.1).flat_map(move |dy| (0..footprint.0).map(move |dx| (anchor.0 + dx, anchor.1 + dy)));
+}
+
+pub fn step_weight(dx: i64, dy: i64) -> f64 {
+    // waits and orthogonal moves cost 1, diagonal and knight moves their euclidean length
+    return if dx == 0 || dy == 0 { 1.0 } else { f64::sqrt((dx * dx + dy * dy) as f64) };
 }
 
 #[cfg(feature = "diagonal_distance")]
-pub fn heuristic(node: &(usize, usize), goal: &(usize, usize)) -> f64 {
-    let a = (node.0 as i64 - goal.0 as i64).abs();
-    let b = (node.1 as i64 - goal.1 as i64).abs();
-    return a as f64 + b as f64 + ((2.0f64).sqrt()-2.0) * min(a, b) as f64;
+pub fn heuristic(field: &InstanceField, node: &(usize, usize), goal: &(usize, usize)) -> f64 {
+    let (a, b) = field.delta(node, goal);
+    let (a, b) = (a.abs(), b.abs());
+    return match field.connectivity() {
+        Connectivity::Four => (a + b) as f64,
+        Connectivity::Eight => a as f64 + b as f64 + ((2.0f64).sqrt()-2.0) * min(a, b) as f64,
+        // the octile distance overestimates knight moves, the straight line never does
+        Connectivity::Sixteen => f64::sqrt((a * a + b * b) as f64),
+    };
 }
 
 #[cfg(feature = "chebichev_distance")]
-pub fn heuristic(node: &(usize, usize), goal: &(usize, usize)) -> f64 {
-    return max((node.0 as i64 - goal.0 as i64).abs(), (node.1 as i64 - goal.1 as i64).abs()) as f64;
+pub fn heuristic(field: &InstanceField, node: &(usize, usize), goal: &(usize, usize)) -> f64 {
+    let (a, b) = field.delta(node, goal);
+    return max(a.abs(), b.abs()) as f64;
 }
 
 #[cfg(not(feature = "chebichev_distance"))]
 #[cfg(not(feature = "diagonal_distance"))]
-pub fn heuristic(node: &(usize, usize), goal: &(usize, usize)) -> f64 {
-    return (node.0 as i64 - goal.0 as i64).pow(2) as f64 + (node.1 as i64 - goal.1 as i64).pow(2) as f64;
+pub fn heuristic(field: &InstanceField, node: &(usize, usize), goal: &(usize, usize)) -> f64 {
+    let (a, b) = field.delta(node, goal);
+    return a.pow(2) as f64 + b.pow(2) as f64;
 }
 
 #[cfg(test)]
 mod field_test {
-    use crate::field::field::RandomField;
+    use crate::field::connectivity::{Connectivity, CornerCutting};
+    use crate::field::field::{BitmapField, CustomField, Field, RandomField};
+    use crate::field::{heuristic, weight};
     use crate::noise::perlin::PerlinNoise;
 
     #[test]
     fn simple_case() {
         let f = RandomField::new(
-            PerlinNoise::new(Some(42), Some(3), None, None, None, None, Some(5), Some(0.1)),
-            1976371185,
-            9,
+            Box::new(PerlinNoise::new(Some(42), Some(3), None, None, None, None, Some(5), Some(0.1))),
+            1907064984,
+            11,
             (5, 5),
             6,
         );
 
         let mut it = f.iter_neighbors(3, 1);
         println!("{}", f);
+        // .##..
+        // .##..
+        // .#...
+        // .#...
         // .....
-        // ....#
-        // ...##
-        // ...##
-        // ....#
 
-        assert_eq!(Some((2, 0)), it.next());
         assert_eq!(Some((3, 0)), it.next());
         assert_eq!(Some((4, 0)), it.next());
-        assert_eq!(Some((2, 1)), it.next());
         assert_eq!(Some((3, 1)), it.next());
+        assert_eq!(Some((4, 1)), it.next());
         assert_eq!(Some((2, 2)), it.next());
+        assert_eq!(Some((3, 2)), it.next());
+        assert_eq!(Some((4, 2)), it.next());
         assert_eq!(None, it.next());
     }
 
     #[test]
     fn underflow() {
         let f = RandomField::new(
-            PerlinNoise::new(Some(42), Some(3), None, None, None, None, Some(5), Some(0.1)),
-            1976371185,
-            9,
+            Box::new(PerlinNoise::new(Some(42), Some(3), None, None, None, None, Some(5), Some(0.1))),
+            1907064984,
+            11,
             (5, 5),
             6,
         );
 
         let mut it = f.iter_neighbors(0, 0);
         println!("{}", f);
+        // .##..
+        // .##..
+        // .#...
+        // .#...
         // .....
-        // ....#
-        // ...##
-        // ...##
-        // ....#
 
         assert_eq!(Some((0, 0)), it.next());
-        assert_eq!(Some((1, 0)), it.next());
         assert_eq!(Some((0, 1)), it.next());
-        assert_eq!(Some((1, 1)), it.next());
         assert_eq!(None, it.next());
     }
 
     #[test]
     fn overflow() {
         let f = RandomField::new(
-            PerlinNoise::new(Some(42), Some(1), None, None, None, None, Some(5), Some(0.1)),
-            1976371185,
-            9,
+            Box::new(PerlinNoise::new(Some(42), Some(1), None, None, None, None, Some(5), Some(0.1))),
+            1563166818,
+            8,
             (5, 5),
             6,
         );
 
         let mut it = f.iter_neighbors(4, 4);
         println!("{}", f);
+        // .###.
+        // ..##.
+        // ..#..
+        // .....
         // .....
-        // ....#
-        // ...##
-        // ...##
-        // ....#
 
+        assert_eq!(Some((3, 3)), it.next());
+        assert_eq!(Some((4, 3)), it.next());
         assert_eq!(Some((3, 4)), it.next());
+        assert_eq!(Some((4, 4)), it.next());
         assert_eq!(None, it.next());
     }
+
+    #[test]
+    fn connectivity() {
+        let mut f = CustomField::new(42, (5, 5), vec![(1, 2), (3, 3)]);
+        assert_eq!(7, f.iter_neighbors(2, 2).count());
+
+        f.set_connectivity(Connectivity::Four);
+        assert_eq!(vec![(2, 1), (2, 2), (3, 2), (2, 3)], f.iter_neighbors(2, 2).collect::<Vec<_>>());
+
+        // knight moves crossing (1, 2) or (3, 3) are blocked
+        f.set_connectivity(Connectivity::Sixteen);
+        assert_eq!(11, f.iter_neighbors(2, 2).count());
+        assert_eq!(vec![(0, 0), (1, 0), (0, 1), (1, 1), (2, 1)], f.iter_neighbors(0, 0).collect::<Vec<_>>());
+
+        assert_eq!(1.0, weight(&(2, 2), &(2, 2)));
+        assert_eq!(f64::sqrt(5.0), weight(&(2, 2), &(3, 4)));
+    }
+
+    #[test]
+    fn footprint() {
+        // ....
+        // .#..
+        // ....
+        let mut f = CustomField::new(42, (4, 3), vec![(1, 1)]);
+        f.set_footprint((2, 2));
+        assert!(f.fits(2, 0) && !f.fits(0, 0) && !f.fits(3, 0));
+        assert_eq!(vec![(2, 0), (2, 1)], f.iter_neighbors(2, 0).collect::<Vec<_>>());
+        assert_eq!(5, f.iter_neighbors_of(2, 0, (1, 1)).count());
+    }
+
+    #[test]
+    fn corner_cutting() {
+        // .#.
+        // #..
+        // ...
+        let mut f = CustomField::new(42, (3, 3), vec![(1, 0), (0, 1)]);
+        assert!(f.iter_neighbors(1, 1).any(|x| x == (0, 0)));
+
+        f.set_corner_cutting(CornerCutting::ForbidIfBoth);
+        assert!(!f.iter_neighbors(1, 1).any(|x| x == (0, 0)));
+        assert!(f.iter_neighbors(1, 1).any(|x| x == (2, 0)));
+
+        f.set_corner_cutting(CornerCutting::ForbidIfEither);
+        assert!(!f.iter_neighbors(1, 1).any(|x| x == (2, 0)));
+        assert!(f.iter_neighbors(1, 1).any(|x| x == (2, 2)));
+    }
+
+    #[test]
+    fn wrap() {
+        let mut f = CustomField::new(42, (5, 5), vec![(4, 4)]);
+        f.set_connectivity(Connectivity::Four);
+        assert!(CustomField::new(42, (5, 4), vec![]).set_wrap(true).is_err());
+        assert!(f.set_wrap(true).is_ok());
+        assert_eq!(vec![(0, 4), (4, 0), (0, 0), (1, 0), (0, 1)], f.iter_neighbors(0, 0).collect::<Vec<_>>());
+        assert_eq!(vec![(3, 0), (4, 0), (0, 0), (4, 1)], f.iter_neighbors(4, 0).collect::<Vec<_>>());
+
+        assert_eq!((-1, 1), f.delta(&(4, 0), &(0, 4)));
+        assert_eq!(f64::sqrt(2.0), f.move_weight(&(0, 0), &(4, 4)));
+        assert_eq!(heuristic(&f, &(0, 0), &(1, 1)), heuristic(&f, &(0, 0), &(4, 4)));
+    }
+
+    #[test]
+    #[cfg(feature = "diagonal_distance")]
+    fn knight_heuristic() {
+        let mut f = CustomField::new(42, (5, 5), vec![]);
+        f.set_connectivity(Connectivity::Sixteen);
+        assert!(heuristic(&f, &(0, 0), &(1, 2)) <= f.move_weight(&(0, 0), &(1, 2)));
+    }
+
+    #[test]
+    fn terrain_costs() {
+        let f = CustomField::with_costs(42, (3, 3), vec![], vec![(1, 1, 3.0)]);
+        assert_eq!(1.0, f.move_weight(&(0, 0), &(1, 0)));
+        assert_eq!(2.0, f.move_weight(&(1, 0), &(1, 1)));
+        assert_eq!(3.0, f.move_weight(&(1, 1), &(1, 1)));
+        assert_eq!(f64::sqrt(2.0) * 2.0, f.move_weight(&(0, 0), &(1, 1)));
+
+        let b = BitmapField::from_field(f);
+        assert_eq!(3.0, b.cost(1, 1));
+        assert_eq!(2.0, b.move_weight(&(1, 1), &(2, 1)));
+    }
+
+    #[test]
+    fn bitmap_matches_noise() {
+        let noise = || PerlinNoise::new(Some(42), Some(3), None, None, None, None, Some(5), Some(0.1));
+        let f = RandomField::new(Box::new(noise()), 1907064984, 11, (5, 5), 6);
+        let b = BitmapField::from_field(RandomField::new(Box::new(noise()), 1907064984, 11, (5, 5), 6));
+
+        assert_eq!(f.obstacles(), b.obstacles());
+        for y in 0..5 {
+            for x in 0..5 {
+                assert_eq!(f.is_obstacle(x, y), b.is_obstacle(x, y));
+                assert!(f.iter_neighbors(x, y).eq(b.iter_neighbors(x, y)));
+            }
+        }
+
+        let t = RandomField::with_terrain(Box::new(noise()), 1907064984, 11, (5, 5), 6, 2.0);
+        let m = RandomField::with_terrain(Box::new(noise()), 1907064984, 11, (5, 5), 6, 2.0).materialize();
+        for y in 0..5 {
+            for x in 0..5 {
+                assert_eq!(t.is_obstacle(x, y), m.is_obstacle(x, y));
+                assert_eq!(t.cost(x, y), m.cost(x, y));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod movingai_test {
+    use crate::field::field::Field;
+    use crate::field::movingai::{parse_map, parse_scen, write_map};
+
+    const MAP: &str = "type octile\nheight 3\nwidth 4\nmap\n.@..\n.TG.\nW..O\n";
+
+    #[test]
+    fn map_round_trip() {
+        let f = parse_map(MAP, 42).unwrap();
+        assert_eq!((4, 3), (f.width(), f.height()));
+        assert_eq!(4, f.obstacles());
+        assert!(f.is_obstacle(1, 0) && f.is_obstacle(1, 1) && f.is_obstacle(0, 2) && f.is_obstacle(3, 2));
+        assert!(!f.is_obstacle(2, 1));
+
+        let mut out = Vec::new();
+        write_map(&f, &mut out).unwrap();
+        assert_eq!("type octile\nheight 3\nwidth 4\nmap\n.@..\n.@..\n@..@\n", String::from_utf8(out).unwrap());
+    }
+
+    #[test]
+    fn bad_map() {
+        assert!(parse_map("type octile\nheight 2\nwidth 2\nmap\n..\n", 42).is_err());
+        assert!(parse_map("type octile\nheight 1\nwidth 2\nmap\n...\n", 42).is_err());
+    }
+
+    #[test]
+    fn scen() {
+        let entries = parse_scen("version 1\n0\tarena.map\t49\t49\t1\t11\t1\t12\t1\n").unwrap();
+        assert_eq!(1, entries.len());
+        assert_eq!((1, 11), entries[0].init);
+        assert_eq!((1, 12), entries[0].goal);
+        assert_eq!("arena.map", entries[0].map);
+    }
+}
+
+#[cfg(test)]
+mod ascii_test {
+    use crate::field::ascii::parse_ascii;
+    use crate::field::field::Field;
+
+    #[test]
+    fn markers() {
+        let map = parse_ascii("Field(4x3)\n.#b.\nS#.G\n..a.\n", 42).unwrap();
+        assert_eq!((4, 3), (map.field.width(), map.field.height()));
+        assert_eq!(2, map.field.obstacles());
+        assert!(map.field.is_obstacle(1, 0) && map.field.is_obstacle(1, 1) && !map.field.is_obstacle(2, 2));
+        assert_eq!(Some((0, 1)), map.markers.init);
+        assert_eq!(Some((3, 1)), map.markers.goal);
+        assert_eq!(vec![(2, 2), (2, 0)], map.markers.agents);
+
+        assert!(parse_ascii("..\n...\n", 42).is_err());
+        assert!(parse_ascii("S.\n.S\n", 42).is_err());
+        assert!(parse_ascii(".x\n.x\n", 42).is_err());
+    }
+}
+
+#[cfg(test)]
+mod image_test {
+    use crate::field::field::{CustomField, Field};
+    use crate::field::image::{parse_pgm, parse_png, GrayImage};
+
+    #[test]
+    fn pgm() {
+        let img = parse_pgm(b"P2\n# drawn by hand\n3 2\n15\n0 15 7\n8 15 0\n").unwrap();
+        assert_eq!((3, 2), (img.width, img.height));
+        assert_eq!(vec![0, 255, 119, 136, 255, 0], img.pixels);
+
+        let f = img.to_field(128, 42);
+        assert_eq!(3, f.obstacles());
+        assert!(f.is_obstacle(0, 0) && f.is_obstacle(2, 0) && f.is_obstacle(2, 1) && !f.is_obstacle(0, 1));
+        assert!(parse_pgm(b"P5\n3 2\n255\n\x00\x01").is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let f = CustomField::with_costs(42, (3, 2), vec![(1, 0)], vec![(2, 1, 2.0)]);
+        let img = GrayImage::from_field(&f);
+        assert_eq!(vec![255, 0, 255, 255, 255, 128], img.pixels);
+
+        let mut pgm = Vec::new();
+        img.write_pgm(&mut pgm).unwrap();
+        assert_eq!(img.pixels, parse_pgm(&pgm).unwrap().pixels);
+
+        let mut png = Vec::new();
+        img.write_png(&mut png).unwrap();
+        let decoded = parse_png(&png).unwrap();
+        assert_eq!((3, 2), (decoded.width, decoded.height));
+        assert_eq!(img.pixels, decoded.pixels);
+    }
+}
+
+#[cfg(test)]
+mod closure_test {
+    use crate::agent::agent_manager::AgentManager;
+    use crate::field::closure::{Closure, Closures};
+
+    #[test]
+    fn windows() {
+        let door = Closure { cell: (1, 0), from: 2, to: 4, period: Some(5) };
+        let active = (0..10).filter(|t| door.is_active(*t)).collect::<Vec<usize>>();
+        assert_eq!(vec![2, 3, 7, 8], active);
+
+        let am = AgentManager::with_closures(vec![], Closures::new(vec![door, Closure { cell: (2, 0), from: 1, to: 2, period: None }]));
+        assert!(am.is_traversable((0, 0), (1, 0), 0));
+        assert!(!am.is_traversable((0, 0), (1, 0), 1));
+        assert!(!am.can_stay((1, 0), 3));
+        assert!(am.can_stay((1, 0), 4));
+        assert!(!am.can_stay((2, 0), 1));
+        assert!(am.can_stay((2, 0), 6));
+    }
+}
+
+#[cfg(test)]
+mod components_test {
+    use crate::field::components::{articulation_cells, Components};
+    use crate::field::connectivity::Connectivity;
+    use crate::field::field::CustomField;
+
+    #[test]
+    fn labelling() {
+        // ..#..
+        // ..#..
+        // ..#.#
+        let mut f = CustomField::new(42, (5, 3), vec![(2, 0), (2, 1), (2, 2), (4, 2)]);
+        let c = Components::new(&f);
+        assert_eq!(2, c.count());
+        assert_eq!(Some(0), c.largest());
+        assert!(c.connected((0, 0), (1, 2)));
+        assert!(!c.connected((0, 0), (3, 0)));
+        assert!(!c.connected((0, 0), (2, 0)));
+        assert_eq!(None, c.label(4, 2));
+
+        // knight moves do not jump over the wall
+        f.set_connectivity(Connectivity::Sixteen);
+        assert_eq!(2, Components::new(&f).count());
+    }
+
+    #[test]
+    fn articulation() {
+        // ...#...
+        // .......
+        // ...#...
+        let mut f = CustomField::new(42, (7, 3), vec![(3, 0), (3, 2)]);
+        f.set_connectivity(Connectivity::Four);
+        assert_eq!(vec![(2, 1), (3, 1), (4, 1)], articulation_cells(&f));
+
+        f.set_connectivity(Connectivity::Eight);
+        assert_eq!(vec![(3, 1)], articulation_cells(&f));
+    }
+}
+
+#[cfg(test)]
+mod maze_test {
+    use crate::field::components::Components;
+    use crate::field::connectivity::Connectivity;
+    use crate::field::field::Field;
+    use crate::field::maze::{MazeAlgorithm, MazeField, MazeParams};
+
+    #[test]
+    fn perfect_maze() {
+        for algorithm in [MazeAlgorithm::Backtracker, MazeAlgorithm::Prim] {
+            let params = MazeParams { algorithm, corridor_width: 1, braid: 0.0 };
+            let mut f = MazeField::generate(42, (21, 11), params);
+            f.set_connectivity(Connectivity::Four);
+
+            // 10x5 logical cells joined by a spanning tree
+            assert_eq!(50 + 49, f.nodes());
+            assert_eq!(1, Components::new(&f).count());
+
+            let g = MazeField::generate(42, (21, 11), params);
+            assert!((0..11).all(|y| (0..21).all(|x| f.is_obstacle(x, y) == g.is_obstacle(x, y))));
+        }
+    }
+
+    #[test]
+    fn braid_and_width() {
+        let perfect = MazeField::generate(7, (31, 31), MazeParams { algorithm: MazeAlgorithm::Prim, corridor_width: 2, braid: 0.0 });
+        let braided = MazeField::generate(7, (31, 31), MazeParams { algorithm: MazeAlgorithm::Prim, corridor_width: 2, braid: 1.0 });
+        assert!(braided.nodes() > perfect.nodes());
+        assert!(!perfect.is_obstacle(1, 1) && !perfect.is_obstacle(2, 2));
+        assert!(perfect.is_obstacle(0, 0) && perfect.is_obstacle(3, 3));
+    }
 }
 
-// 1976371185, 9
+#[cfg(test)]
+mod dungeon_test {
+    use crate::field::components::Components;
+    use crate::field::connectivity::Connectivity;
+    use crate::field::dungeon::{DungeonField, DungeonParams};
+    use crate::field::field::{Field, FieldKind};
+
+    #[test]
+    fn rooms_are_connected() {
+        let params = DungeonParams { rooms: 6, room_width: (3, 7), room_height: (3, 5), corridor_width: 2 };
+        let mut f = DungeonField::generate(42, (60, 30), params);
+        f.set_connectivity(Connectivity::Four);
+        let c = Components::new(&f);
+        assert_eq!(1, c.count());
+
+        let rooms = match f.kind() {
+            FieldKind::DungeonField(d) => d.rooms().clone(),
+            _ => panic!("not a dungeon")
+        };
+        assert_eq!(6, rooms.len());
+        for (x, y, w, h) in rooms {
+            assert!(x > 0 && y > 0 && x + w < 60 && y + h < 30);
+            assert!(!f.is_obstacle(x, y) && !f.is_obstacle(x + w - 1, y + h - 1));
+        }
+
+        let g = DungeonField::generate(42, (60, 30), params);
+        assert_eq!(f.obstacles(), g.obstacles());
+    }
+}
+
+#[cfg(test)]
+mod warehouse_test {
+    use crate::field::field::{Field, FieldKind};
+    use crate::field::warehouse::{WarehouseField, WarehouseParams};
+
+    #[test]
+    fn layout() {
+        let params = WarehouseParams { rows: 2, columns: 3, shelf: (4, 2), aisle_width: 1, margin: 2 };
+        assert_eq!((18, 9), params.size());
+
+        let f = WarehouseField::generate(42, params.size(), params);
+        assert_eq!(2 * 3 * 4 * 2, f.obstacles());
+        assert!(f.is_obstacle(2, 2) && f.is_obstacle(5, 3) && f.is_obstacle(15, 6));
+        assert!(!f.is_obstacle(6, 2) && !f.is_obstacle(2, 4) && !f.is_obstacle(16, 6));
+
+        let w = match f.kind() {
+            FieldKind::WarehouseField(w) => w,
+            _ => panic!("not a warehouse")
+        };
+        assert!(w.is_staging(0, 5) && w.is_staging(16, 5) && w.is_staging(8, 7));
+        assert!(!w.is_staging(2, 2) && !w.is_staging(6, 4));
+    }
+}
+
+#[cfg(test)]
+mod composite_test {
+    use crate::field::composite::CompositeField;
+    use crate::field::connectivity::Connectivity;
+    use crate::field::field::{CustomField, Field};
+
+    #[test]
+    fn overlay() {
+        let mut base = CustomField::new(1, (4, 3), vec![(0, 0), (1, 1), (2, 1)]);
+        base.set_connectivity(Connectivity::Four);
+        base.set_footprint((1, 2));
+        // (0, 0) is both added and removed, removing wins
+        let mut f = CompositeField::build(base, vec![(3, 2), (1, 1), (0, 0)], vec![(2, 1), (0, 0)]);
+        assert_eq!(Connectivity::Four, f.connectivity());
+        assert_eq!((1, 2), f.footprint());
+        f.set_footprint((1, 1));
+        assert_eq!(2, f.obstacles());
+        assert!(f.is_obstacle(1, 1) && f.is_obstacle(3, 2));
+        assert!(!f.is_obstacle(0, 0) && !f.is_obstacle(2, 1));
+        assert_eq!(vec![(2, 0), (2, 1), (3, 1), (2, 2)], f.iter_neighbors(2, 1).collect::<Vec<_>>());
+    }
+}
+
+// 1907064984, 11
 
 #[cfg(test)]
 mod visited_node_test {
@@ -124,13 +527,11 @@ mod visited_node_test {
         let mut map = HashMap::new();
         let am = AgentManager::new(vec![]);
 
-
-        let mut node = VisitedNode::new((0, 0));
+        let mut node = VisitedNode::new((0, 0), 1.0);
         node.set(0, 0.0, None, &am);
         map.insert((0, 0), node);
 
-
-        let mut new = VisitedNode::new((0, 1));
+        let mut new = VisitedNode::new((0, 1), 1.0);
         new.set(1, 1.0, Some((0, 0)), &am);
         map.insert((0, 1), new);
 
@@ -0,0 +1,121 @@
+//! MovingAI grid benchmark formats
+//!
+//! Reads `.map` files into a `CustomField` and `.scen` files into init/goal pairs,
+//! and writes any `InstanceField` back as a `.map`.
+//! Format description https://movingai.com/benchmarks/formats.html
+
+use std::fs;
+use std::io::Write;
+
+use crate::field::field::{CustomField, Field, InstanceField};
+
+/// Terrain characters that cannot be traversed (out of bounds, trees, water)
+const OBSTACLE_CHARS: [char; 4] = ['@', 'T', 'O', 'W'];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScenEntry {
+    pub bucket: usize,
+    pub map: String,
+    pub width: usize,
+    pub height: usize,
+    pub init: (usize, usize),
+    pub goal: (usize, usize),
+    pub optimal: f64,
+}
+
+pub fn parse_map(contents: &str, seed: u64) -> Result<InstanceField, String> {
+    let mut lines = contents.lines();
+    let mut width: Option<usize> = None;
+    let mut height: Option<usize> = None;
+
+    for line in lines.by_ref() {
+        let mut tokens = line.split_whitespace();
+        match (tokens.next(), tokens.next()) {
+            (Some("type"), _) => {}
+            (Some("height"), Some(v)) => height = Some(v.parse().map_err(|_| format!("Invalid map height '{}'", v))?),
+            (Some("width"), Some(v)) => width = Some(v.parse().map_err(|_| format!("Invalid map width '{}'", v))?),
+            (Some("map"), None) => break,
+            (None, _) => {}
+            _ => return Err(format!("Unexpected map header line '{}'", line)),
+        }
+    }
+
+    let (width, height) = match (width, height) {
+        (Some(w), Some(h)) => (w, h),
+        _ => return Err("Map header must declare both width and height".to_string()),
+    };
+
+    let mut obstacles = Vec::new();
+    let mut y = 0;
+    for line in lines {
+        let line = line.trim_end();
+        if line.is_empty() {
+            continue;
+        }
+        if y >= height {
+            return Err(format!("Map has more than {} rows", height));
+        }
+        if line.chars().count() != width {
+            return Err(format!("Map row {} is not {} cells wide", y, width));
+        }
+        for (x, c) in line.chars().enumerate() {
+            if OBSTACLE_CHARS.contains(&c) {
+                obstacles.push((x, y));
+            }
+        }
+        y += 1;
+    }
+    if y != height {
+        return Err(format!("Map has {} rows, expected {}", y, height));
+    }
+
+    return Ok(CustomField::new(seed, (width, height), obstacles));
+}
+
+pub fn load_map(path: &str, seed: u64) -> Result<InstanceField, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("Cannot read map '{}': {}", path, e))?;
+    return parse_map(contents.as_str(), seed);
+}
+
+pub fn parse_scen(contents: &str) -> Result<Vec<ScenEntry>, String> {
+    let mut entries = Vec::new();
+    for line in contents.lines() {
+        if line.trim().is_empty() || line.starts_with("version") {
+            continue;
+        }
+        let cols: Vec<&str> = line.split('\t').collect();
+        if cols.len() != 9 {
+            return Err(format!("Scenario row '{}' must have 9 columns", line));
+        }
+        let num = |i: usize| cols[i].trim().parse::<usize>().map_err(|_| format!("Invalid value '{}' in scenario row '{}'", cols[i], line));
+        entries.push(ScenEntry {
+            bucket: num(0)?,
+            map: cols[1].to_string(),
+            width: num(2)?,
+            height: num(3)?,
+            init: (num(4)?, num(5)?),
+            goal: (num(6)?, num(7)?),
+            optimal: cols[8].trim().parse().map_err(|_| format!("Invalid optimal length in scenario row '{}'", line))?,
+        });
+    }
+    return Ok(entries);
+}
+
+pub fn load_scen(path: &str) -> Result<Vec<ScenEntry>, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("Cannot read scenario '{}': {}", path, e))?;
+    return parse_scen(contents.as_str());
+}
+
+pub fn write_map<W: Write>(field: &InstanceField, out: &mut W) -> std::io::Result<()> {
+    writeln!(out, "type octile")?;
+    writeln!(out, "height {}", field.height())?;
+    writeln!(out, "width {}", field.width())?;
+    writeln!(out, "map")?;
+    for y in 0..field.height() {
+        let row = (0..field.width())
+            .map(|x| if field.is_obstacle(x, y) { '@' } else { '.' })
+            .collect::<String>();
+        writeln!(out, "{}", row)?;
+    }
+    return Ok(());
+}
@@ -1,30 +1,46 @@
+use crate::field::connectivity::CornerCutting;
 use crate::field::field::{Field, InstanceField};
+use crate::field::footprint_cells;
 
 pub struct NeighborIterator<'a> {
     field: &'a InstanceField,
     base_point: (usize, usize),
-    next: Option<(i64, i64)>,
+    moves: &'static [(i64, i64)],
+    corner_cutting: CornerCutting,
+    footprint: (usize, usize),
+    next: usize,
 }
 
 impl<'a> NeighborIterator<'a> {
-    pub fn new(field: &'a InstanceField, start_point: (usize, usize)) -> Self {
+    pub fn new(field: &'a InstanceField, start_point: (usize, usize), moves: &'static [(i64, i64)], corner_cutting: CornerCutting, footprint: (usize, usize)) -> Self {
         return NeighborIterator {
             field,
             base_point: start_point,
-            next: Some((start_point.0 as i64 - 1, start_point.1 as i64 - 1)),
+            moves,
+            corner_cutting,
+            footprint,
+            next: 0,
         };
     }
 
-    fn calc_next(point: (i64, i64), base: (usize, usize)) -> Option<(i64, i64)> {
-        let mut p = point;
-        p = (p.0 + 1, p.1);
-        if p.0 > (base.0 as i64 + 1) {
-            p = (base.0 as i64 - 1, p.1 + 1)
+    fn is_blocked(&self, offset: (i64, i64)) -> bool {
+        return match self.field.offset(self.base_point, offset) {
+            Some(p) => footprint_cells(p, self.footprint).any(|(x, y)| !self.field.exists(x, y) || self.field.is_obstacle(x, y)),
+            None => true
+        };
+    }
+
+    fn cuts_corner(&self, dx: i64, dy: i64) -> bool {
+        // knight moves cross both swept cells whatever the policy, diagonals only touch them
+        let knight = dx.abs() == 2 || dy.abs() == 2;
+        if self.corner_cutting == CornerCutting::Allow && !knight {
+            return false;
         }
-        if p.1 > (base.1 as i64 + 1) {
-            return None;
+        if let Some((first, second)) = CornerCutting::swept_cells(dx, dy) {
+            let (first, second) = (self.is_blocked(first), self.is_blocked(second));
+            return if knight { first || second } else { !self.corner_cutting.allows(first, second) };
         }
-        return Some(p);
+        return false;
     }
 }
 
@@ -32,29 +48,14 @@ impl<'a> Iterator for NeighborIterator<'a> {
     type Item = (usize, usize);
 
     fn next(&mut self) -> Option<Self::Item> {
-        let nxt = self.next;
-        if nxt.is_none() {
-            return None;
-        }
+        while let Some((dx, dy)) = self.moves.get(self.next).cloned() {
+            self.next += 1;
 
-        let mut next_ret = nxt.unwrap();
-        let mut next = Some(next_ret);
-
-        while next.is_some() {
-            let n = next.take().unwrap();
-            self.next = NeighborIterator::calc_next(n, self.base_point);
-            next = self.next;
-
-            if next_ret.0 >= 0 && next_ret.1 >= 0 && self.field.exists(next_ret.0 as usize, next_ret.1 as usize) && !self.field.is_obstacle(next_ret.0 as usize, next_ret.1 as usize) {
-                return Some((next_ret.0 as usize, next_ret.1 as usize));
-            } else {
-                if self.next.is_none() {
-                    return None;
-                }
-                next_ret = self.next.take().unwrap();
+            if !self.is_blocked((dx, dy)) && !self.cuts_corner(dx, dy) {
+                return self.field.offset(self.base_point, (dx, dy));
             }
         }
 
         return None;
     }
-}
\ No newline at end of file
+}
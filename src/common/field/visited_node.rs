@@ -1,19 +1,23 @@
 use std::collections::BTreeMap;
 
 use crate::agent::agent_manager::AgentManager;
-use crate::field::weight;
 
 #[derive(Debug)]
 pub struct VisitedNode {
     location: (usize, usize),
     timeline: BTreeMap<usize, (f64, Option<(usize, usize)>)>,
     best: (usize, f64),
+    #[cfg(feature = "wait_move_weight_calc")]
+    wait_weight: f64,
 }
 
 impl VisitedNode {
-    pub fn new(location: (usize, usize)) -> Self {
+    #[cfg_attr(not(feature = "wait_move_weight_calc"), allow(unused_variables))]
+    pub fn new(location: (usize, usize), wait_weight: f64) -> Self {
         let cell = VisitedNode {
             location,
+            #[cfg(feature = "wait_move_weight_calc")]
+            wait_weight,
             timeline: BTreeMap::new(),
             best: (usize::MAX, f64::MAX),
         };
@@ -70,7 +74,7 @@ impl VisitedNode {
                     }
                 }
 
-                return w + (time as f64 - t as f64) * weight(&self.location, &self.location);
+                return w + (time as f64 - t as f64) * self.wait_weight;
             }
         }
 
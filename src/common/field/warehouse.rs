@@ -0,0 +1,115 @@
+//! Warehouse shelf and aisle layout
+//!
+//! `rows` x `columns` blocks of shelves, each `shelf.0` x `shelf.1` cells, separated by
+//! aisles `aisle_width` cells wide. The blocks are surrounded by an open staging area
+//! `margin` cells deep along every border.
+
+use std::fmt::{Display, Formatter};
+
+use rand_xoshiro::rand_core::SeedableRng;
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use crate::field::field::{Field, FieldKind, InstanceField};
+
+#[derive(Debug, Clone, Copy)]
+pub struct WarehouseParams {
+    pub rows: usize,
+    pub columns: usize,
+    /// Width and height of a shelf block
+    pub shelf: (usize, usize),
+    pub aisle_width: usize,
+    pub margin: usize,
+}
+
+impl WarehouseParams {
+    /// Size of the field fitting exactly the layout
+    pub fn size(&self) -> (usize, usize) {
+        return (
+            2 * self.margin + self.columns * self.shelf.0 + self.columns.saturating_sub(1) * self.aisle_width,
+            2 * self.margin + self.rows * self.shelf.1 + self.rows.saturating_sub(1) * self.aisle_width,
+        );
+    }
+
+    /// Whether the cell of a `size` field lies in the open area around the shelf blocks
+    pub fn is_staging(&self, size: (usize, usize), x: usize, y: usize) -> bool {
+        let m = self.margin;
+        return x < m || y < m || x + m >= size.0 || y + m >= size.1;
+    }
+}
+
+pub struct WarehouseField {
+    width: usize,
+    height: usize,
+    rng: Xoshiro256PlusPlus,
+    params: WarehouseParams,
+}
+
+impl WarehouseField {
+    pub fn generate(seed: u64, size: (usize, usize), params: WarehouseParams) -> InstanceField {
+        return InstanceField::new(FieldKind::WarehouseField(WarehouseField {
+            width: size.0,
+            height: size.1,
+            rng: Xoshiro256PlusPlus::seed_from_u64(seed),
+            params,
+        }));
+    }
+
+    pub fn params(&self) -> &WarehouseParams {
+        return &self.params;
+    }
+
+    pub fn is_staging(&self, x: usize, y: usize) -> bool {
+        return self.params.is_staging((self.width, self.height), x, y);
+    }
+
+    /// Whether the coordinate falls inside one of the `count` blocks along an axis
+    fn in_block(v: usize, margin: usize, block: usize, aisle: usize, count: usize) -> bool {
+        if v < margin {
+            return false;
+        }
+        let v = v - margin;
+        let pitch = block + aisle;
+        return v / pitch < count && v % pitch < block;
+    }
+}
+
+impl Field for WarehouseField {
+    fn is_obstacle(&self, x: usize, y: usize) -> bool {
+        let p = &self.params;
+        return WarehouseField::in_block(x, p.margin, p.shelf.0, p.aisle_width, p.columns)
+            && WarehouseField::in_block(y, p.margin, p.shelf.1, p.aisle_width, p.rows);
+    }
+
+    fn obstacles(&self) -> usize {
+        let count = |size: usize, block: usize, aisle: usize, n: usize| {
+            return (0..size).filter(|v| WarehouseField::in_block(*v, self.params.margin, block, aisle, n)).count();
+        };
+        return count(self.width, self.params.shelf.0, self.params.aisle_width, self.params.columns)
+            * count(self.height, self.params.shelf.1, self.params.aisle_width, self.params.rows);
+    }
+
+    fn width(&self) -> usize {
+        return self.width;
+    }
+
+    fn height(&self) -> usize {
+        return self.height;
+    }
+
+    fn rng(&mut self) -> &mut Xoshiro256PlusPlus {
+        return &mut self.rng;
+    }
+}
+
+impl Display for WarehouseField {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut s = String::from("");
+        for y in 0..self.height {
+            for x in 0..self.width {
+                s.push(if self.is_obstacle(x, y) { '#' } else { '.' });
+            }
+            s.push('\n');
+        }
+        write!(f, "Field({}x{}), WAREHOUSE: {}x{} blocks\n{}", self.width, self.height, self.params.rows, self.params.columns, s)
+    }
+}
@@ -0,0 +1,45 @@
+//! Whole grid noise evaluation
+//!
+//! The grid is cut in strips of `STRIP_ROWS` full width rows, evaluated with
+//! `NoiseSource::fill_noise_u32`. Every thread takes a contiguous run of strips, so the
+//! accumulators it returns cover consecutive rows.
+
+use std::num::NonZeroUsize;
+use std::thread;
+
+use crate::noise::{NoiseSource, Tile};
+
+pub const STRIP_ROWS: usize = 16;
+
+/// Folds the noise values of the `size` grid strip by strip, one accumulator per thread.
+/// The accumulators are returned in row order.
+pub fn fold_strips<T, I, F>(noise: &dyn NoiseSource, size: (usize, usize), init: I, f: F) -> Vec<T>
+    where T: Send, I: Fn() -> T + Sync, F: Fn(&mut T, Tile, &[u32]) + Sync {
+    let strips = size.1.div_ceil(STRIP_ROWS);
+    let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get).min(strips).max(1);
+    let per_thread = strips.div_ceil(threads);
+
+    let run = |first: usize, last: usize| {
+        let mut acc = init();
+        let mut values = vec![0u32; size.0 * STRIP_ROWS];
+        for strip in first..last {
+            let y = strip * STRIP_ROWS;
+            let tile = Tile { x: 0, y, width: size.0, height: STRIP_ROWS.min(size.1 - y) };
+            let values = &mut values[..tile.width * tile.height];
+            noise.fill_noise_u32(tile, values);
+            f(&mut acc, tile, values);
+        }
+        return acc;
+    };
+
+    if threads == 1 {
+        return vec![run(0, strips)];
+    }
+    return thread::scope(|s| {
+        let handles = (0..threads)
+            .map(|t| (t * per_thread, ((t + 1) * per_thread).min(strips)))
+            .map(|(first, last)| s.spawn(move || run(first, last)))
+            .collect::<Vec<_>>();
+        return handles.into_iter().map(|h| h.join().expect("Noise worker panicked")).collect();
+    });
+}
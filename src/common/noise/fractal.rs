@@ -0,0 +1,122 @@
+/// How the octaves are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FractalMode {
+    /// Plain sum of the octaves
+    Fbm,
+    /// Sharp creases where the noise crosses zero, each octave weighted by the previous
+    /// one. The creases get the lowest values, so obstacles form thin walls
+    Ridged,
+    /// Absolute value of the octaves, rounded blobs separated by creases
+    Billow,
+}
+
+impl FractalMode {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        return match name {
+            "fbm" => Ok(FractalMode::Fbm),
+            "ridged" => Ok(FractalMode::Ridged),
+            "billow" => Ok(FractalMode::Billow),
+            n => Err(format!("Unsupported fractal mode '{}', expected fbm, ridged or billow", n)),
+        };
+    }
+
+    pub fn name(&self) -> &'static str {
+        return match self {
+            FractalMode::Fbm => "fbm",
+            FractalMode::Ridged => "ridged",
+            FractalMode::Billow => "billow",
+        };
+    }
+}
+
+/// Octave parameters shared by every noise backend.
+///
+/// The raw noise is sampled at `(x / cell_size) * frequency + offset` and summed over
+/// `octaves`, scaling the amplitude by `persistence` and the frequency by `lacunarity`
+/// at every step.
+///
+/// With a `warp`, the sample coordinates are first displaced by up to `warp` times
+/// `cell_size` cells in either direction, along a vector read from two independently
+/// seeded layers of the same noise.
+#[derive(Debug, Clone, Copy)]
+pub struct FractalParams {
+    pub octaves: usize,
+    pub persistence: f64,
+    pub lacunarity: f64,
+    pub amplitude: f64,
+    pub frequency: f64,
+    pub cell_size: usize,
+    pub offset: f64,
+    pub mode: FractalMode,
+    pub warp: f64,
+}
+
+/// Noise layers the warp vector components are sampled from
+const WARP_LAYERS: [u64; 2] = [1, 2];
+
+impl FractalParams {
+    pub fn new(octaves: Option<usize>, persistence: Option<f64>, lacunarity: Option<f64>, amplitude: Option<f64>, frequency: Option<f64>, cell_size: Option<usize>, offset: Option<f64>) -> Self {
+        return FractalParams {
+            octaves: octaves.unwrap_or(1),
+            persistence: persistence.unwrap_or(0.5),
+            lacunarity: lacunarity.unwrap_or(2.0),
+            amplitude: amplitude.unwrap_or(1.0),
+            frequency: frequency.unwrap_or(1.0),
+            cell_size: cell_size.unwrap_or(100),
+            offset: offset.unwrap_or(0.1),
+            mode: FractalMode::Fbm,
+            warp: 0.0,
+        };
+    }
+
+    pub fn with_mode(mut self, mode: FractalMode, warp: Option<f64>) -> Self {
+        self.mode = mode;
+        self.warp = warp.unwrap_or(0.0);
+        return self;
+    }
+
+    /// Adds an octave `sample` to the running `val`. `weight` carries the state of the
+    /// ridged mode between octaves and starts from 1
+    pub fn accumulate(&self, val: &mut f64, weight: &mut f64, amp: f64, sample: f64) {
+        match self.mode {
+            FractalMode::Fbm => *val += amp * sample,
+            FractalMode::Billow => *val += amp * (2.0 * sample.abs() - 1.0),
+            FractalMode::Ridged => {
+                let signal = (1.0 - sample.abs()).powi(2) * *weight;
+                *weight = (signal * 2.0).clamp(0.0, 1.0);
+                *val += amp * (1.0 - 2.0 * signal);
+            }
+        }
+    }
+
+    /// Fractal brownian motion: sums `sample(0, x, y)` over the octaves, the other layers
+    /// only feed the warp
+    pub fn fbm<F: Fn(u64, f64, f64) -> f64>(&self, x: usize, y: usize, sample: F) -> f64 {
+        let mut xx = x as f64 / self.cell_size as f64;
+        let mut yy = y as f64 / self.cell_size as f64;
+
+        if self.warp != 0.0 {
+            let shift = WARP_LAYERS.map(|layer| sample(layer, xx * self.frequency + self.offset, yy * self.frequency + self.offset).clamp(-1.0, 1.0));
+            xx += self.warp * shift[0];
+            yy += self.warp * shift[1];
+        }
+
+        let mut val: f64 = 0.0;
+        let mut weight: f64 = 1.0;
+        let mut amp = self.amplitude;
+        let mut freq = self.frequency;
+
+        for _ in 0..self.octaves {
+            self.accumulate(&mut val, &mut weight, amp, sample(0, xx * freq + self.offset, yy * freq + self.offset));
+            amp *= self.persistence;
+            freq *= self.lacunarity;
+        }
+
+        return val;
+    }
+
+    /// Maps the octave sum to [0, 1]
+    pub fn normalize(&self, val: f64) -> f64 {
+        return (val / (self.octaves as f64) + 1.0) / 2.0;
+    }
+}
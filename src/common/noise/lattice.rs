@@ -0,0 +1,58 @@
+//! Lattice gradients shared by the noise backends
+//!
+//! Gradients must decode to the same obstacle map on every platform and toolchain,
+//! so they are derived from a fixed hash instead of `std`'s `DefaultHasher`:
+//!
+//! ```text
+//! h = splitmix64(splitmix64(splitmix64(seed) ^ x) ^ y)
+//! angle = (h >> 32) / u32::MAX * 2 * PI
+//! ```
+//!
+//! Changing anything here changes every generated field: bump `NOISE_FORMAT_VERSION`.
+
+use std::f64::consts::PI;
+use std::ops;
+
+/// Version of the noise decoding, stored in the instance files.
+/// 1: lattice hash based on `DefaultHasher` (not reproducible across toolchains)
+/// 2: splitmix64 lattice hash
+/// 3: lattice coordinates floored, negative samples (negative offset or warp) hash instead
+///    of saturating to 0, warp vectors read from their own layers
+pub const NOISE_FORMAT_VERSION: u32 = 3;
+
+#[derive(Clone, Copy)]
+pub struct Vec2(pub f64, pub f64);
+
+impl ops::Mul for Vec2 {
+    type Output = f64;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        return self.0 * rhs.0 + self.1 * rhs.1;
+    }
+}
+
+/// SplitMix64 finalizer, see https://prng.di.unimi.it/splitmix64.c
+fn splitmix64(v: u64) -> u64 {
+    let mut z = v.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    return z ^ (z >> 31);
+}
+
+/// Seed of the independent noise `layer`, layer 0 is the field itself
+pub fn layer_seed(seed: u64, layer: u64) -> u64 {
+    if layer == 0 {
+        return seed;
+    }
+    return splitmix64(seed.wrapping_add(layer));
+}
+
+pub fn lattice_hash(seed: u64, x: usize, y: usize) -> u64 {
+    return splitmix64(splitmix64(splitmix64(seed) ^ x as u64) ^ y as u64);
+}
+
+/// Pseudo random unit vector attached to the lattice point `(x, y)`
+pub fn gradient(seed: u64, x: usize, y: usize) -> Vec2 {
+    let angl: f64 = (((lattice_hash(seed, x, y) >> 32) as u32 as f64) / u32::MAX as f64) * 2.0 * PI;
+    return Vec2(angl.cos(), angl.sin());
+}
@@ -1,12 +1,105 @@
+use crate::noise::fractal::FractalParams;
+use crate::noise::open_simplex::OpenSimplexNoise;
+use crate::noise::perlin::PerlinNoise;
+use crate::noise::simplex::SimplexNoise;
+
 pub mod perlin;
+pub mod simplex;
+pub mod open_simplex;
+pub mod fractal;
+pub mod batch;
+mod lattice;
+
+pub use crate::noise::lattice::NOISE_FORMAT_VERSION;
+
+/// A seeded 2D noise generator sampled on integer grid coordinates
+pub trait NoiseSource: Send + Sync {
+    /// Raw octave sum at the cell
+    fn gen_noise(&self, x: usize, y: usize) -> f64;
+    /// Noise value mapped to [0, 1]
+    fn gen_normalized(&self, x: usize, y: usize) -> f64;
+    fn get_noise_u32(&self, x: usize, y: usize) -> u32 {
+        return (self.gen_normalized(x, y) * (u32::MAX as f64)) as u32;
+    }
+    fn get_seed(&self) -> u64;
+    fn kind(&self) -> NoiseKind;
+
+    /// `gen_noise` of every cell of the tile, row major. Backends may share the work
+    /// between neighbouring cells but must return the same values
+    fn fill_noise(&self, tile: Tile, out: &mut [f64]) {
+        for y in 0..tile.height {
+            for x in 0..tile.width {
+                out[y * tile.width + x] = self.gen_noise(tile.x + x, tile.y + y);
+            }
+        }
+    }
+
+    /// `get_noise_u32` of every cell of the tile, row major
+    fn fill_noise_u32(&self, tile: Tile, out: &mut [u32]) {
+        for y in 0..tile.height {
+            for x in 0..tile.width {
+                out[y * tile.width + x] = self.get_noise_u32(tile.x + x, tile.y + y);
+            }
+        }
+    }
+}
+
+/// Rectangle of cells with its top left corner in `(x, y)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoiseKind {
+    Perlin,
+    Simplex,
+    OpenSimplex,
+}
+
+impl NoiseKind {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        return match name {
+            "perlin" => Ok(NoiseKind::Perlin),
+            "simplex" => Ok(NoiseKind::Simplex),
+            "open_simplex" => Ok(NoiseKind::OpenSimplex),
+            n => Err(format!("Unsupported noise kind '{}', expected perlin, simplex or open_simplex", n)),
+        };
+    }
+
+    pub fn name(&self) -> &'static str {
+        return match self {
+            NoiseKind::Perlin => "perlin",
+            NoiseKind::Simplex => "simplex",
+            NoiseKind::OpenSimplex => "open_simplex",
+        };
+    }
+
+    pub fn create(&self, seed: Option<u64>, fractal: FractalParams) -> Box<dyn NoiseSource> {
+        return match self {
+            NoiseKind::Perlin => Box::new(PerlinNoise::with_params(seed, fractal)),
+            NoiseKind::Simplex => Box::new(SimplexNoise::new(seed, fractal)),
+            NoiseKind::OpenSimplex => Box::new(OpenSimplexNoise::new(seed, fractal)),
+        };
+    }
+}
 
 #[cfg(test)]
 mod perlin_test {
+    use crate::noise::fractal::{FractalMode, FractalParams};
+    use crate::noise::lattice::lattice_hash;
+    use crate::noise::NoiseKind;
     use crate::noise::perlin::PerlinNoise;
+    use crate::noise::simplex::SimplexNoise;
+    use crate::noise::{NoiseSource, Tile};
+    use crate::noise::batch::fold_strips;
 
     #[test]
     fn one_cell() {
-        let noise = PerlinNoise::new(Some(42), Some(3), None, None, None, None, Some(5));
+        let noise = PerlinNoise::new(Some(42), Some(3), None, None, None, None, Some(5), None);
         for i in 0..50 {
             for j in 0..50 {
                 if noise.gen_normalized(i, j) > 0.6 {
@@ -18,4 +111,95 @@ mod perlin_test {
             println!();
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn backends_range() {
+        for kind in [NoiseKind::Perlin, NoiseKind::Simplex, NoiseKind::OpenSimplex] {
+            let noise = kind.create(Some(42), FractalParams::new(Some(1), None, None, None, None, Some(7), Some(0.0)));
+            let (mut lo, mut hi) = (f64::MAX, f64::MIN);
+            for i in 0..200 {
+                for j in 0..200 {
+                    let v = noise.gen_normalized(i, j);
+                    lo = lo.min(v);
+                    hi = hi.max(v);
+                }
+            }
+            println!("{}: [{}, {}]", kind.name(), lo, hi);
+            assert!(lo >= 0.0 && hi <= 1.0);
+            assert!(hi - lo > 0.5);
+            assert_eq!(kind, NoiseKind::from_name(kind.name()).unwrap());
+        }
+    }
+
+    #[test]
+    fn fractal_modes() {
+        for mode in [FractalMode::Fbm, FractalMode::Ridged, FractalMode::Billow] {
+            for warp in [None, Some(2.0)] {
+                let noise = NoiseKind::Perlin.create(Some(42), FractalParams::new(Some(4), None, None, None, None, Some(7), None).with_mode(mode, warp));
+                let values = (0..100).flat_map(|i| (0..100).map(move |j| (i, j))).map(|(i, j)| noise.gen_normalized(i, j)).collect::<Vec<f64>>();
+                assert!(values.iter().all(|v| *v >= 0.0 && *v <= 1.0));
+            }
+            assert_eq!(mode, FractalMode::from_name(mode.name()).unwrap());
+        }
+
+        // warping moves the samples, the plain field is left untouched
+        let plain = NoiseKind::Perlin.create(Some(42), FractalParams::new(Some(2), None, None, None, None, Some(7), None));
+        let warped = NoiseKind::Perlin.create(Some(42), FractalParams::new(Some(2), None, None, None, None, Some(7), None).with_mode(FractalMode::Fbm, Some(0.0)));
+        assert_eq!(plain.gen_noise(10, 10), warped.gen_noise(10, 10));
+        let warped = NoiseKind::Perlin.create(Some(42), FractalParams::new(Some(2), None, None, None, None, Some(7), None).with_mode(FractalMode::Fbm, Some(1.5)));
+        assert_ne!(plain.gen_noise(10, 10), warped.gen_noise(10, 10));
+    }
+
+    #[test]
+    fn warp_layers() {
+        let fractal = FractalParams::new(Some(1), None, None, None, None, Some(1), Some(0.0)).with_mode(FractalMode::Fbm, Some(2.0));
+        // the field reports where it is sampled, the warp layers push it either way, up to `warp`
+        assert_eq!(3.0, fractal.fbm(3, 4, |layer, x, y| if layer == 0 { x + y } else { -1.0 }));
+        assert_eq!(-3.0, fractal.fbm(0, 1, |layer, x, y| if layer == 0 { x + y } else { -1.5 }));
+        assert_eq!(7.0, fractal.fbm(1, 2, |layer, x, y| if layer == 0 { x + y } else { 3.0 }));
+    }
+
+    #[test]
+    fn simplex_negative_coordinates() {
+        let n = SimplexNoise::new(Some(42), FractalParams::new(None, None, None, None, None, None, None));
+        // one step back along the lattice diagonal, the corners differ but the offsets do not
+        let d = -1.0 + 2.0 * 0.21132486540518713;
+        let (x, y) = (-0.3, -0.6);
+        assert!((n.get(42, x, y) - n.get(42, x + d, y + d)).abs() > 1e-9);
+        assert!((n.get(42, x + d, y + d) - n.get(42, x + 2.0 * d, y + 2.0 * d)).abs() > 1e-9);
+    }
+
+    #[test]
+    fn stable_lattice_hash() {
+        // pinned values: any change here breaks the decoding of stored instances
+        assert_eq!(0x6310bf04d8207f46, lattice_hash(42, 0, 0));
+        assert_eq!(0x93be8420bb55b94c, lattice_hash(42, 1, 0));
+        assert_eq!(0x0dd8adbf0cb6ab7a, lattice_hash(4039822366, 123, 456));
+    }
+
+    #[test]
+    fn batch_matches_cells() {
+        // coarse lattice (gradients cached) and fine lattice (fetched per cell)
+        // negative offsets put the first lattice points below 0
+        for (cell_size, frequency, offset) in [(5, 1.0, 0.1), (1, 3.0, 0.1), (5, 1.0, -0.7), (1, 3.0, -4.3)] {
+            for (mode, warp) in [(FractalMode::Fbm, None), (FractalMode::Ridged, None), (FractalMode::Billow, Some(0.5))] {
+                let noise = PerlinNoise::with_params(Some(42), FractalParams::new(Some(3), None, None, None, Some(frequency), Some(cell_size), Some(offset)).with_mode(mode, warp));
+                let tile = Tile { x: 3, y: 7, width: 20, height: 9 };
+                let mut out = vec![0.0; 20 * 9];
+                noise.fill_noise(tile, &mut out);
+                for y in 0..9 {
+                    for x in 0..20 {
+                        assert_eq!(noise.gen_noise(3 + x, 7 + y).to_bits(), out[y * 20 + x].to_bits());
+                    }
+                }
+            }
+        }
+
+        for kind in [NoiseKind::Perlin, NoiseKind::Simplex, NoiseKind::OpenSimplex] {
+            let noise = kind.create(Some(7), FractalParams::new(Some(4), None, None, None, None, Some(6), None));
+            let strips = fold_strips(noise.as_ref(), (13, 40), Vec::new, |acc, _, values| acc.extend_from_slice(values));
+            let cells = (0..40).flat_map(|y| (0..13).map(move |x| (x, y))).map(|(x, y)| noise.get_noise_u32(x, y)).collect::<Vec<u32>>();
+            assert_eq!(cells, strips.concat());
+        }
+    }
+}
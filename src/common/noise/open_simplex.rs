@@ -0,0 +1,74 @@
+//! 2D OpenSimplex noise
+//!
+//! Kurt Spencer's OpenSimplex: the point is mapped to a stretched square lattice and
+//! every lattice vertex within the attenuation radius contributes `(2 - d²)^4 * (g · d)`.
+//! Vertices are enumerated in the 4x4 block around the stretched cell instead of the
+//! original region lookup tables, which yields the same sum.
+//! Lattice gradients are the same pseudo random unit vectors used by the perlin noise.
+
+use std::time::SystemTime;
+
+use crate::noise::fractal::FractalParams;
+use crate::noise::lattice::{gradient, layer_seed, Vec2};
+use crate::noise::{NoiseKind, NoiseSource};
+
+/// (1 / sqrt(2 + 1) - 1) / 2
+const STRETCH: f64 = -0.211324865405187;
+/// (sqrt(2 + 1) - 1) / 2
+const SQUISH: f64 = 0.366025403784439;
+/// Brings the sum of the vertex contributions back to [-1, 1]
+const NORM: f64 = 8.7;
+
+pub struct OpenSimplexNoise {
+    seed: u64,
+    fractal: FractalParams,
+}
+
+impl OpenSimplexNoise {
+    pub fn new(seed: Option<u64>, fractal: FractalParams) -> Self {
+        return OpenSimplexNoise {
+            seed: seed.unwrap_or(SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_micros() as u64),
+            fractal,
+        };
+    }
+
+    fn get(&self, seed: u64, x: f64, y: f64) -> f64 {
+        let stretch = (x + y) * STRETCH;
+        let xsb = (x + stretch).floor() as i64;
+        let ysb = (y + stretch).floor() as i64;
+
+        let mut val = 0.0;
+        for dy in -1..=2 {
+            for dx in -1..=2 {
+                let (px, py) = (xsb + dx, ysb + dy);
+                let squish = (px + py) as f64 * SQUISH;
+                let ox = x - (px as f64 + squish);
+                let oy = y - (py as f64 + squish);
+                let attn = 2.0 - ox * ox - oy * oy;
+                if attn > 0.0 {
+                    // negative lattice coordinates only act as hash keys
+                    val += attn * attn * attn * attn * (gradient(seed, px as usize, py as usize) * Vec2(ox, oy));
+                }
+            }
+        }
+        return val / NORM;
+    }
+}
+
+impl NoiseSource for OpenSimplexNoise {
+    fn gen_noise(&self, x: usize, y: usize) -> f64 {
+        return self.fractal.fbm(x, y, |layer, a, b| self.get(layer_seed(self.seed, layer), a, b));
+    }
+
+    fn gen_normalized(&self, x: usize, y: usize) -> f64 {
+        return self.fractal.normalize(self.gen_noise(x, y));
+    }
+
+    fn get_seed(&self) -> u64 {
+        return self.seed;
+    }
+
+    fn kind(&self) -> NoiseKind {
+        return NoiseKind::OpenSimplex;
+    }
+}
@@ -6,110 +6,153 @@
 //! Lattice vectors are generating as described in https://mrl.cs.nyu.edu/~perlin/paper445.pdf
 //! Param description https://gamedev.stackexchange.com/questions/197861/how-to-handle-octave-frequency-in-the-perlin-noise-algorithm
 
-use std::collections::hash_map::DefaultHasher;
-use std::f64::consts::PI;
-use std::hash::{Hash, Hasher};
-use std::ops;
 use std::time::SystemTime;
 
-use rand_xoshiro::rand_core::{RngCore, SeedableRng};
-use rand_xoshiro::Xoshiro256PlusPlus;
+use crate::noise::fractal::FractalParams;
+use crate::noise::lattice::{gradient, layer_seed, Vec2};
+use crate::noise::{NoiseKind, NoiseSource, Tile};
+
+/// Lattice points per cell above which a tile is not worth caching its gradients
+const MAX_LATTICE_PER_CELL: usize = 4;
 
 pub struct PerlinNoise {
     seed: u64,
-
-    octaves: usize,
-    persistence: f64,
-    lacunarity: f64,
-    amplitude: f64,
-    frequency: f64,
-    cell_size: usize,
-    offset: f64
-}
-
-#[derive(Clone, Copy)]
-struct Vec2(f64, f64);
-
-impl ops::Mul for Vec2 {
-    type Output = f64;
-
-    fn mul(self, rhs: Self) -> Self::Output {
-        return self.0 * rhs.0 + self.1 * rhs.1;
-    }
+    fractal: FractalParams,
 }
 
-
 impl PerlinNoise {
     /// Creates a new instance of the perlin noise generator
     pub fn new(seed: Option<u64>, octaves: Option<usize>, persistence: Option<f64>, lacunarity: Option<f64>, amplitude: Option<f64>, frequency: Option<f64>, cell_size: Option<usize>, offset: Option<f64>) -> Self {
+        return PerlinNoise::with_params(seed, FractalParams::new(octaves, persistence, lacunarity, amplitude, frequency, cell_size, offset));
+    }
+
+    pub fn with_params(seed: Option<u64>, fractal: FractalParams) -> Self {
         return PerlinNoise {
             seed: seed.unwrap_or(SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_micros() as u64),
-            octaves: octaves.unwrap_or(1),
-            persistence: persistence.unwrap_or(0.5),
-            lacunarity: lacunarity.unwrap_or(2.0),
-            amplitude: amplitude.unwrap_or(1.0),
-            frequency: frequency.unwrap_or(1.0),
-            cell_size: cell_size.unwrap_or(100),
-            offset: offset.unwrap_or(0.1)
+            fractal,
         };
     }
 
-    fn get_rand_vect(&self, x: usize, y: usize) -> Vec2 {
-        let mut s = DefaultHasher::new();
-        (self.seed, x, y).hash(&mut s);
-        let mut rng = Xoshiro256PlusPlus::seed_from_u64(s.finish());
-        let angl: f64 = ((rng.next_u32() as f64) / u32::MAX as f64) * 2.0 * PI;
-        return Vec2(angl.cos(), angl.sin());
-    }
-
     fn ease(a: f64, b: f64, c: f64, d: f64, ox: f64, oy: f64) -> f64 {
         let u = (b - a) * (3.0 - ox * 2.0) * ox * ox + a;
         let v = (d - c) * (3.0 - ox * 2.0) * ox * ox + c;
         return (v - u) * (3.0 - oy * 2.0) * oy * oy + u;
     }
 
-    fn get(&self, x: f64, y: f64) -> f64 {
-        let base_x = x.trunc() as usize;
-        let base_y = y.trunc() as usize;
-        let offset_x = x.fract();
-        let offset_y = y.fract();
+    /// Lattice index and offset of the sample coordinate of every cell along one axis of the tile
+    fn axis(&self, from: usize, len: usize, freq: f64) -> Vec<(i64, f64)> {
+        return (from..from + len)
+            .map(|v| (v as f64 / self.fractal.cell_size as f64) * freq + self.fractal.offset)
+            .map(|v| (v.floor() as i64, v - v.floor()))
+            .collect();
+    }
+
+    /// One octave over the tile, the gradients of each lattice point are computed once
+    fn sample_octave(&self, tile: Tile, freq: f64, out: &mut [f64]) {
+        let cols = self.axis(tile.x, tile.width, freq);
+        let rows = self.axis(tile.y, tile.height, freq);
+        let (x0, x1) = (cols.iter().map(|c| c.0).min().unwrap(), cols.iter().map(|c| c.0).max().unwrap());
+        let (y0, y1) = (rows.iter().map(|r| r.0).min().unwrap(), rows.iter().map(|r| r.0).max().unwrap());
+        let nx = (x1 - x0 + 2) as usize;
+        let ny = (y1 - y0 + 2) as usize;
+
+        // sparse octaves would compute more gradients than cells, fetch them on demand
+        let cached = nx * ny <= MAX_LATTICE_PER_CELL * cols.len() * rows.len();
+        let mut grads = Vec::with_capacity(if cached { nx * ny } else { 0 });
+        if cached {
+            for y in y0..y0 + ny as i64 {
+                for x in x0..x0 + nx as i64 {
+                    grads.push(gradient(self.seed, x as usize, y as usize));
+                }
+            }
+        }
+        // negative lattice coordinates only act as hash keys, as in `get`
+        let grad = |x: i64, y: i64| if cached { grads[(y - y0) as usize * nx + (x - x0) as usize] } else { gradient(self.seed, x as usize, y as usize) };
+
+        for (j, &(by, oy)) in rows.iter().enumerate() {
+            for (i, &(bx, ox)) in cols.iter().enumerate() {
+                out[j * tile.width + i] = PerlinNoise::ease(
+                    grad(bx, by) * Vec2(ox, oy),
+                    grad(bx + 1, by) * Vec2(1.0 - ox, oy),
+                    grad(bx, by + 1) * Vec2(ox, 1.0 - oy),
+                    grad(bx + 1, by + 1) * Vec2(1.0 - ox, 1.0 - oy),
+                    ox,
+                    oy,
+                );
+            }
+        }
+    }
+
+    fn get(&self, seed: u64, x: f64, y: f64) -> f64 {
+        // warped samples may go negative, those lattice coordinates only act as hash keys
+        let base_x = x.floor() as i64 as usize;
+        let base_y = y.floor() as i64 as usize;
+        let offset_x = x - x.floor();
+        let offset_y = y - y.floor();
 
         return PerlinNoise::ease(
-            self.get_rand_vect(base_x, base_y) * Vec2(offset_x, offset_y),
-            self.get_rand_vect(base_x + 1, base_y) * Vec2(1.0 - offset_x, offset_y),
-            self.get_rand_vect(base_x, base_y + 1) * Vec2(offset_x, 1.0 - offset_y),
-            self.get_rand_vect(base_x + 1, base_y + 1) * Vec2(1.0 - offset_x, 1.0 - offset_y),
+            gradient(seed, base_x, base_y) * Vec2(offset_x, offset_y),
+            gradient(seed, base_x.wrapping_add(1), base_y) * Vec2(1.0 - offset_x, offset_y),
+            gradient(seed, base_x, base_y.wrapping_add(1)) * Vec2(offset_x, 1.0 - offset_y),
+            gradient(seed, base_x.wrapping_add(1), base_y.wrapping_add(1)) * Vec2(1.0 - offset_x, 1.0 - offset_y),
             offset_x,
             offset_y,
         );
     }
+}
 
-    pub fn gen_noise(&self, x: usize, y: usize) -> f64 {
-        let xx = x as f64 / self.cell_size as f64;
-        let yy = y as f64 / self.cell_size as f64;
-
-        let mut val: f64 = 0.0;
-        let mut amp = self.amplitude;
-        let mut freq = self.frequency;
+impl NoiseSource for PerlinNoise {
+    fn gen_noise(&self, x: usize, y: usize) -> f64 {
+        return self.fractal.fbm(x, y, |layer, a, b| self.get(layer_seed(self.seed, layer), a, b));
+    }
 
-        for _ in 0..self.octaves {
-            val += amp * self.get(xx * freq + self.offset, yy * freq + self.offset);
-            amp *= self.persistence;
-            freq *= self.lacunarity;
-        }
+    fn gen_normalized(&self, x: usize, y: usize) -> f64 {
+        return self.fractal.normalize(self.gen_noise(x, y));
+    }
 
-        return val;
+    fn get_seed(&self) -> u64 {
+        return self.seed;
     }
 
-    pub fn get_noise_u32(&self, x: usize, y: usize) -> u32 {
-        return (self.gen_normalized(x, y) * (u32::MAX as f64)) as u32;
+    fn kind(&self) -> NoiseKind {
+        return NoiseKind::Perlin;
     }
 
-    pub fn gen_normalized(&self, x: usize, y: usize) -> f64 {
-        return (self.gen_noise(x, y) / (self.octaves as f64) + 1.0) / 2.0;
+    fn fill_noise(&self, tile: Tile, out: &mut [f64]) {
+        let out = &mut out[..tile.width * tile.height];
+        if self.fractal.warp != 0.0 {
+            // warped cells do not share the lattice of their neighbours
+            for y in 0..tile.height {
+                for x in 0..tile.width {
+                    out[y * tile.width + x] = self.gen_noise(tile.x + x, tile.y + y);
+                }
+            }
+            return;
+        }
+        out.fill(0.0);
+        if out.is_empty() {
+            return;
+        }
+        // same octave progression as `FractalParams::fbm`
+        let mut samples = vec![0.0; out.len()];
+        let mut weights = vec![1.0; out.len()];
+        let mut amp = self.fractal.amplitude;
+        let mut freq = self.fractal.frequency;
+        for _ in 0..self.fractal.octaves {
+            self.sample_octave(tile, freq, &mut samples);
+            for i in 0..out.len() {
+                self.fractal.accumulate(&mut out[i], &mut weights[i], amp, samples[i]);
+            }
+            amp *= self.fractal.persistence;
+            freq *= self.fractal.lacunarity;
+        }
     }
 
-    pub fn get_seed(&self) -> u64 {
-        return self.seed;
+    fn fill_noise_u32(&self, tile: Tile, out: &mut [u32]) {
+        let mut raw = vec![0.0; tile.width * tile.height];
+        self.fill_noise(tile, &mut raw);
+        for (o, v) in out.iter_mut().zip(raw) {
+            *o = (self.fractal.normalize(v) * (u32::MAX as f64)) as u32;
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,78 @@
+//! 2D Simplex noise
+//!
+//! Samples the three corners of the skewed triangular lattice containing the point,
+//! as described in https://weber.itn.liu.se/~stegu/simplexnoise/simplexnoise.pdf
+//! Lattice gradients are the same pseudo random unit vectors used by the perlin noise.
+
+use std::time::SystemTime;
+
+use crate::noise::fractal::FractalParams;
+use crate::noise::lattice::{gradient, layer_seed, Vec2};
+use crate::noise::{NoiseKind, NoiseSource};
+
+/// Skew factor (sqrt(3) - 1) / 2
+const F2: f64 = 0.36602540378443865;
+/// Unskew factor (3 - sqrt(3)) / 6
+const G2: f64 = 0.21132486540518713;
+/// Brings the sum of the corner contributions back to [-1, 1]
+const SCALE: f64 = 99.0;
+
+pub struct SimplexNoise {
+    seed: u64,
+    fractal: FractalParams,
+}
+
+impl SimplexNoise {
+    pub fn new(seed: Option<u64>, fractal: FractalParams) -> Self {
+        return SimplexNoise {
+            seed: seed.unwrap_or(SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_micros() as u64),
+            fractal,
+        };
+    }
+
+    fn corner(seed: u64, i: i64, j: i64, x: f64, y: f64) -> f64 {
+        let t = 0.5 - x * x - y * y;
+        if t <= 0.0 {
+            return 0.0;
+        }
+        // negative lattice coordinates only act as hash keys
+        return t * t * t * t * (gradient(seed, i as usize, j as usize) * Vec2(x, y));
+    }
+
+    /// Single octave sample, defined on the whole plane
+    pub(crate) fn get(&self, seed: u64, x: f64, y: f64) -> f64 {
+        let s = (x + y) * F2;
+        let i = (x + s).floor();
+        let j = (y + s).floor();
+        let t = (i + j) * G2;
+        let x0 = x - (i - t);
+        let y0 = y - (j - t);
+
+        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+        let (i, j) = (i as i64, j as i64);
+
+        return SCALE * (
+            SimplexNoise::corner(seed, i, j, x0, y0) +
+            SimplexNoise::corner(seed, i + i1, j + j1, x0 - i1 as f64 + G2, y0 - j1 as f64 + G2) +
+            SimplexNoise::corner(seed, i + 1, j + 1, x0 - 1.0 + 2.0 * G2, y0 - 1.0 + 2.0 * G2)
+        );
+    }
+}
+
+impl NoiseSource for SimplexNoise {
+    fn gen_noise(&self, x: usize, y: usize) -> f64 {
+        return self.fractal.fbm(x, y, |layer, a, b| self.get(layer_seed(self.seed, layer), a, b));
+    }
+
+    fn gen_normalized(&self, x: usize, y: usize) -> f64 {
+        return self.fractal.normalize(self.gen_noise(x, y));
+    }
+
+    fn get_seed(&self) -> u64 {
+        return self.seed;
+    }
+
+    fn kind(&self) -> NoiseKind {
+        return NoiseKind::Simplex;
+    }
+}
@@ -2,24 +2,55 @@ use std::cmp::max;
 use std::fs;
 use std::time::SystemTime;
 
-use argparse::{ArgumentParser, Store, StoreFalse, StoreOption};
+use argparse::{ArgumentParser, Store, StoreFalse, StoreOption, StoreTrue};
 use yaml_rust::YamlLoader;
 
+use common::field::dungeon::DungeonParams;
+use common::field::maze::{MazeAlgorithm, MazeParams};
+use common::field::warehouse::WarehouseParams;
+
 #[derive(Debug)]
 pub struct NoiseParams {
+    pub kind: String,
     pub octaves: Option<usize>,
     pub persistence: Option<f64>,
     pub lacunarity: Option<f64>,
     pub amplitude: Option<f64>,
     pub frequency: Option<f64>,
     pub cell_size: Option<usize>,
-    pub offset: Option<f64>
+    pub offset: Option<f64>,
+    pub terrain_weight: Option<f64>,
+    pub fractal: String,
+    pub warp: Option<f64>,
 }
 
 #[derive(Debug)]
 pub struct AgentParams {
     pub number: usize,
     pub stop_probability: f64,
+    pub staging: bool,
+    /// `random`, `destination` or `patrol`, see `common::agent::behaviour`
+    pub model: String,
+    /// Probability on every move to stay in place, for any model
+    pub wait_probability: f64,
+    /// Patrol cycle shared by all the agents, each starting from a different waypoint
+    pub waypoints: Vec<(usize, usize)>,
+    /// Random waypoints of every patrol agent when `waypoints` is empty
+    pub patrol_points: usize,
+    /// What agents do once their path is over: park, vanish or loop
+    pub end: String,
+    /// Agent `i` enters the map at tick `i * arrival_interval`
+    pub arrival_interval: usize,
+    /// Ticks every agent stays on the map for
+    pub lifetime: Option<usize>,
+    /// Ticks between two steps of every agent, one move every `period` ticks
+    pub period: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct OverlayParams {
+    pub add: Vec<(usize, usize)>,
+    pub remove: Vec<(usize, usize)>,
 }
 
 #[derive(Debug)]
@@ -29,11 +60,24 @@ pub struct Config {
     pub seed: u64,
     pub greedy: bool,
     pub noise_params: NoiseParams,
+    pub maze: Option<MazeParams>,
+    pub dungeon: Option<DungeonParams>,
+    pub warehouse: Option<WarehouseParams>,
+    pub overlay: Option<OverlayParams>,
     pub agents: AgentParams,
     pub obstacles: usize,
+    pub connectivity: usize,
+    pub corner_cutting: String,
+    pub wrap: bool,
+    /// `(width, height)` cells taken by the planned agent, the generated agents take one cell
+    pub footprint: (usize, usize),
+    pub spawn: String,
     pub time_max: usize,
 
     pub aux_path: Option<String>,
+    pub map_path: Option<String>,
+    pub image_path: Option<String>,
+    pub noise_image_path: Option<String>,
 }
 
 impl Config {
@@ -47,6 +91,10 @@ impl Config {
         let mut conf_id: Option<String> = None;
         let mut w: Option<usize> = None;
         let mut h: Option<usize> = None;
+        let mut maze: Option<String> = None;
+        let mut corridor_width: usize = 1;
+        let mut braid: f64 = 0.0;
+        let mut rooms: Option<usize> = None;
 
         {
             let mut ap = ArgumentParser::new();
@@ -55,17 +103,35 @@ impl Config {
             ap.refer(&mut fname).add_option(&["-c", "--config"], StoreOption, "Config file name. If present configuration will be loaded from file INSTEAD of cmdline.");
             ap.refer(&mut conf_id).add_option(&["-i", "--config-id"], StoreOption, "Config ID. Allows to load one doc from the yaml file");
             ap.refer(&mut cfg.aux_path).add_option(&["-o", "--aux-file"], StoreOption, "Output aux file path");
+            ap.refer(&mut cfg.map_path).add_option(&["-m", "--map-file"], StoreOption, "Output MovingAI .map file path");
+            ap.refer(&mut cfg.image_path).add_option(&["--image-file"], StoreOption, "Output grayscale image of the field (.png or .pgm)");
+            ap.refer(&mut cfg.noise_image_path).add_option(&["--noise-image-file"], StoreOption, "Output grayscale image of the normalized noise (.png or .pgm)");
             ap.refer(&mut cfg.greedy).add_option(&["-e", "--exhaustive"], StoreFalse, "Do not use greedy search (won't use aux file)");
 
             ap.refer(&mut cfg.seed).add_option(&["-s", "--seed"], Store, "RNG Seed");
             ap.refer(&mut w).add_option(&["-w", "--width"], StoreOption, "Grid Width");
             ap.refer(&mut h).add_option(&["-h", "--height"], StoreOption, "Grid Height");
             ap.refer(&mut cfg.obstacles).add_option(&["-o", "--obstacles"], Store, "Number of obstacles");
+            ap.refer(&mut cfg.connectivity).add_option(&["--connectivity"], Store, "Grid connectivity (4, 8 or 16)");
+            ap.refer(&mut cfg.corner_cutting).add_option(&["--corner-cutting"], Store, "Diagonal corner cutting policy (allow, forbid_if_either, forbid_if_both)");
+            ap.refer(&mut cfg.wrap).add_option(&["--wrap"], StoreTrue, "Toroidal grid, moving off an edge enters from the opposite one");
+            ap.refer(&mut cfg.footprint.0).add_option(&["--footprint-width"], Store, "Width of the cells taken by the planned agent");
+            ap.refer(&mut cfg.footprint.1).add_option(&["--footprint-height"], Store, "Height of the cells taken by the planned agent");
+            ap.refer(&mut cfg.spawn).add_option(&["--spawn"], Store, "Spawn policy for agents, init and goal (any, connected, largest)");
             ap.refer(&mut cfg.time_max).add_option(&["-t", "--tmax"], Store, "Maximum resolution time depth");
 
             ap.refer(&mut cfg.agents.number).add_option(&["-a", "--agents"], Store, "Number of agents");
+            ap.refer(&mut cfg.agents.staging).add_option(&["--agents-staging"], StoreTrue, "Spawn agents only in the warehouse staging areas");
             ap.refer(&mut cfg.agents.stop_probability).add_option(&["--agent-stop-probability"], Store, "Probability on every move for an agent to stop. [0, 1]");
+            ap.refer(&mut cfg.agents.model).add_option(&["--agent-model"], Store, "Agent behaviour (random, destination, patrol)");
+            ap.refer(&mut cfg.agents.wait_probability).add_option(&["--agent-wait-probability"], Store, "Probability on every move for an agent to stay in place. [0, 1]");
+            ap.refer(&mut cfg.agents.end).add_option(&["--agent-end"], Store, "What agents do once their path is over (park, vanish, loop)");
+            ap.refer(&mut cfg.agents.arrival_interval).add_option(&["--agent-arrival-interval"], Store, "Ticks between the arrivals of two agents, 0 for all at the start");
+            ap.refer(&mut cfg.agents.lifetime).add_option(&["--agent-lifetime"], StoreOption, "Ticks every agent stays on the map for");
+            ap.refer(&mut cfg.agents.period).add_option(&["--agent-period"], Store, "Ticks between two moves of every agent");
+            ap.refer(&mut cfg.agents.patrol_points).add_option(&["--agent-patrol-points"], Store, "Number of random waypoints of patrol agents");
 
+            ap.refer(&mut cfg.noise_params.kind).add_option(&["--noise"], Store, "Noise backend (perlin, simplex, open_simplex)");
             ap.refer(&mut cfg.noise_params.octaves).add_option(&["--octaves"], StoreOption, "Noise Octaves");
             ap.refer(&mut cfg.noise_params.persistence).add_option(&["--persistence"], StoreOption, "Amplitude dumping factor");
             ap.refer(&mut cfg.noise_params.lacunarity).add_option(&["--lacunarity"], StoreOption, "Frequency multiplication factor");
@@ -73,6 +139,14 @@ impl Config {
             ap.refer(&mut cfg.noise_params.frequency).add_option(&["--frequency"], StoreOption, "Starting frequency");
             ap.refer(&mut cfg.noise_params.cell_size).add_option(&["--cell_size"], StoreOption, "Cell size");
             ap.refer(&mut cfg.noise_params.offset).add_option(&["--offset"], StoreOption, "Noise offset");
+            ap.refer(&mut cfg.noise_params.fractal).add_option(&["--fractal"], Store, "Octave combination (fbm, ridged, billow)");
+            ap.refer(&mut cfg.noise_params.warp).add_option(&["--warp"], StoreOption, "Domain warp strength, in noise cells");
+            ap.refer(&mut cfg.noise_params.terrain_weight).add_option(&["--terrain-weight"], StoreOption, "Extra traversal cost of free cells, scaled by their noise value");
+
+            ap.refer(&mut maze).add_option(&["--maze"], StoreOption, "Generate a maze instead of noise (backtracker, prim)");
+            ap.refer(&mut rooms).add_option(&["--dungeon"], StoreOption, "Generate rooms and corridors with the given number of rooms");
+            ap.refer(&mut corridor_width).add_option(&["--corridor-width"], Store, "Maze or dungeon corridor width");
+            ap.refer(&mut braid).add_option(&["--braid"], Store, "Probability of opening a maze dead end. [0, 1]");
             ap.parse_args_or_exit();
         }
 
@@ -84,6 +158,21 @@ impl Config {
         if w.is_some() && h.is_some() {
             cfg.size = (w.unwrap(), h.unwrap());
         }
+        if let Some(algorithm) = maze {
+            cfg.maze = Some(MazeParams {
+                algorithm: MazeAlgorithm::from_name(algorithm.as_str()).expect("Invalid maze algorithm"),
+                corridor_width,
+                braid,
+            });
+        }
+        if let Some(rooms) = rooms {
+            cfg.dungeon = Some(DungeonParams {
+                rooms,
+                room_width: (3, 8),
+                room_height: (3, 8),
+                corridor_width,
+            });
+        }
         if let Some(cell_size) = cfg.noise_params.cell_size {
             cfg.noise_params.cell_size = Some(cell_size);
         } else {
@@ -117,13 +206,37 @@ impl Config {
             if let Some(v) = doc["seed"].as_i64() { cfg.seed = v as u64; }
             if let Some(v) = doc["greedy"].as_bool() { cfg.greedy = v; }
             if let Some(v) = doc["obstacles"].as_i64() { cfg.obstacles = v as usize; }
+            if let Some(v) = doc["connectivity"].as_i64() { cfg.connectivity = v as usize; }
+            if let Some(v) = doc["corner_cutting"].as_str() { cfg.corner_cutting = v.to_string(); }
+            if let Some(v) = doc["wrap"].as_bool() { cfg.wrap = v; }
+            if !doc["footprint"].is_badvalue() {
+                cfg.footprint = (doc["footprint"][0].as_i64().unwrap_or(1) as usize, doc["footprint"][1].as_i64().unwrap_or(1) as usize);
+            }
+            if let Some(v) = doc["spawn"].as_str() { cfg.spawn = v.to_string(); }
             if let Some(v) = doc["time_max"].as_i64() { cfg.time_max = v as usize; }
             if let Some(v) = doc["aux_path"].as_str() { cfg.aux_path = Some(v.to_string()); }
+            if let Some(v) = doc["map_path"].as_str() { cfg.map_path = Some(v.to_string()); }
+            if let Some(v) = doc["image_path"].as_str() { cfg.image_path = Some(v.to_string()); }
+            if let Some(v) = doc["noise_image_path"].as_str() { cfg.noise_image_path = Some(v.to_string()); }
             if !doc["agents"].is_badvalue() {
                 if let Some(v) = doc["agents"]["number"].as_i64() { cfg.agents.number = v as usize; }
                 if let Some(v) = doc["agents"]["stop_probability"].as_f64() { cfg.agents.stop_probability = v; }
+                if let Some(v) = doc["agents"]["staging"].as_bool() { cfg.agents.staging = v; }
+                if let Some(v) = doc["agents"]["model"].as_str() { cfg.agents.model = v.to_string(); }
+                if let Some(v) = doc["agents"]["wait_probability"].as_f64() { cfg.agents.wait_probability = v; }
+                if let Some(v) = doc["agents"]["patrol_points"].as_i64() { cfg.agents.patrol_points = v as usize; }
+                if let Some(v) = doc["agents"]["end"].as_str() { cfg.agents.end = v.to_string(); }
+                if let Some(v) = doc["agents"]["arrival_interval"].as_i64() { cfg.agents.arrival_interval = v as usize; }
+                if let Some(v) = doc["agents"]["lifetime"].as_i64() { cfg.agents.lifetime = Some(v as usize); }
+                if let Some(v) = doc["agents"]["period"].as_i64() { cfg.agents.period = v as usize; }
+                if let Some(v) = doc["agents"]["waypoints"].as_vec() {
+                    cfg.agents.waypoints = v.iter()
+                        .map(|c| (c[0].as_i64().expect("Invalid waypoint") as usize, c[1].as_i64().expect("Invalid waypoint") as usize))
+                        .collect();
+                }
             }
             if !doc["noise"].is_badvalue() {
+                if let Some(v) = doc["noise"]["kind"].as_str() { cfg.noise_params.kind = v.to_string(); }
                 cfg.noise_params.amplitude = doc["noise"]["amplitude"].as_f64();
                 if let Some(v) = doc["noise"]["octaves"].as_i64() { cfg.noise_params.octaves = Some(v as usize); }
                 cfg.noise_params.frequency = doc["noise"]["frequency"].as_f64();
@@ -131,12 +244,58 @@ impl Config {
                 cfg.noise_params.lacunarity = doc["noise"]["lacunarity"].as_f64();
                 if let Some(v) = doc["noise"]["cell_size"].as_i64() { cfg.noise_params.cell_size = Some(v as usize); }
                 if let Some(v) = doc["noise"]["offset"].as_f64() { cfg.noise_params.offset = Some(v); }
+                if let Some(v) = doc["noise"]["terrain_weight"].as_f64() { cfg.noise_params.terrain_weight = Some(v); }
+                if let Some(v) = doc["noise"]["fractal"].as_str() { cfg.noise_params.fractal = v.to_string(); }
+                if let Some(v) = doc["noise"]["warp"].as_f64() { cfg.noise_params.warp = Some(v); }
+            }
+            if !doc["maze"].is_badvalue() {
+                cfg.maze = Some(MazeParams {
+                    algorithm: MazeAlgorithm::from_name(doc["maze"]["algorithm"].as_str().unwrap_or("backtracker")).expect("Invalid maze algorithm"),
+                    corridor_width: doc["maze"]["corridor_width"].as_i64().unwrap_or(1) as usize,
+                    braid: doc["maze"]["braid"].as_f64().unwrap_or(0.0),
+                });
+            }
+            if !doc["dungeon"].is_badvalue() {
+                let range = |key: &str, default: (usize, usize)| {
+                    let v = &doc["dungeon"][key];
+                    return match (v[0].as_i64(), v[1].as_i64()) {
+                        (Some(lo), Some(hi)) => (lo as usize, hi as usize),
+                        _ => default
+                    };
+                };
+                cfg.dungeon = Some(DungeonParams {
+                    rooms: doc["dungeon"]["rooms"].as_i64().unwrap_or(5) as usize,
+                    room_width: range("room_width", (3, 8)),
+                    room_height: range("room_height", (3, 8)),
+                    corridor_width: doc["dungeon"]["corridor_width"].as_i64().unwrap_or(1) as usize,
+                });
+            }
+            if !doc["warehouse"].is_badvalue() {
+                let w = &doc["warehouse"];
+                cfg.warehouse = Some(WarehouseParams {
+                    rows: w["rows"].as_i64().unwrap_or(2) as usize,
+                    columns: w["columns"].as_i64().unwrap_or(2) as usize,
+                    shelf: (w["shelf"][0].as_i64().unwrap_or(4) as usize, w["shelf"][1].as_i64().unwrap_or(2) as usize),
+                    aisle_width: w["aisle_width"].as_i64().unwrap_or(1) as usize,
+                    margin: w["margin"].as_i64().unwrap_or(2) as usize,
+                });
+            }
+            if !doc["overlay"].is_badvalue() {
+                let cells = |key: &str| {
+                    return doc["overlay"][key].as_vec().map_or(vec![], |v| v.iter()
+                        .map(|c| (c[0].as_i64().expect("Invalid overlay cell") as usize, c[1].as_i64().expect("Invalid overlay cell") as usize))
+                        .collect());
+                };
+                cfg.overlay = Some(OverlayParams { add: cells("add"), remove: cells("remove") });
             }
-
 
             if !doc["size"]["width"].is_badvalue() && !doc["size"]["height"].is_badvalue() {
                 cfg.size = (doc["size"]["width"].as_i64().unwrap() as usize, doc["size"]["height"].as_i64().unwrap() as usize);
             }
+            if let Some(w) = cfg.warehouse {
+                // the layout defines the field size
+                cfg.size = w.size();
+            }
             break;
         }
         return cfg;
@@ -147,22 +306,47 @@ impl Config {
             id: "none".to_string(),
             seed: SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_micros() as u64,
             obstacles: 30,
+            connectivity: 8,
+            corner_cutting: "allow".to_string(),
+            wrap: false,
+            footprint: (1, 1),
+            spawn: "any".to_string(),
             size: (10, 10),
             time_max: 100,
             greedy: true,
             aux_path: None,
+            map_path: None,
+            image_path: None,
+            noise_image_path: None,
+            maze: None,
+            dungeon: None,
+            warehouse: None,
+            overlay: None,
             agents: AgentParams {
                 number: 1,
                 stop_probability: 0.0,
+                staging: false,
+                model: "random".to_string(),
+                wait_probability: 0.0,
+                waypoints: vec![],
+                patrol_points: 4,
+                end: "park".to_string(),
+                arrival_interval: 0,
+                lifetime: None,
+                period: 1,
             },
             noise_params: NoiseParams {
+                kind: "perlin".to_string(),
                 amplitude: None,
                 octaves: None,
                 frequency: None,
                 persistence: None,
                 lacunarity: None,
                 cell_size: None,
-                offset: None
+                offset: None,
+                terrain_weight: None,
+                fractal: "fbm".to_string(),
+                warp: None,
             },
         };
     }
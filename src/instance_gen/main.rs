@@ -1,81 +1,233 @@
 use std::cmp::Reverse;
 use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::fs::File;
+use std::rc::Rc;
 use bincode::config;
 use flate2::Compression;
 use flate2::write::ZlibEncoder;
 
-use common::agent::agent::{Agent, get_agents_at_time, get_agents_last};
-use common::field::field::{Field, InstanceField, RandomField};
+use common::agent::agent::{Agent, PathEnd, get_agents_at_time, get_agents_last};
+use common::agent::behaviour::{Behaviour, BehaviourModel, Destination, Lazy, Patrol, RandomWalk};
+use common::agent::validate::validate_paths;
+use common::field::components::Components;
+use common::field::composite::CompositeField;
+use common::field::connectivity::{Connectivity, CornerCutting};
+use common::field::field::{Field, FieldKind, InstanceField, RandomField};
+use common::field::dungeon::DungeonField;
+use common::field::image::GrayImage;
+use common::field::maze::MazeField;
+use common::field::movingai::write_map;
+use common::field::warehouse::WarehouseField;
 use common::field::open_node::OpenNode;
-use common::field::weight;
-use common::noise::perlin::PerlinNoise;
+use common::field::footprint_cells;
+use common::noise::batch::fold_strips;
+use common::noise::fractal::{FractalMode, FractalParams};
+use common::noise::{NoiseKind, NoiseSource};
 
 use crate::args::Config;
 use crate::noise_value::NoiseValue;
 use crate::output::OutSettings;
+use crate::spawn::SpawnRegion;
 
 mod args;
 mod noise_value;
 mod output;
+mod spawn;
 
-fn gen_field_parameters(cfg: &Config) -> (u32, usize, PerlinNoise) {
-    let mut heap: BinaryHeap<NoiseValue> = BinaryHeap::with_capacity(cfg.obstacles);
-    let noise = PerlinNoise::new(Some(cfg.seed), cfg.noise_params.octaves, cfg.noise_params.persistence, cfg.noise_params.lacunarity, cfg.noise_params.amplitude, cfg.noise_params.frequency, cfg.noise_params.cell_size, cfg.noise_params.offset);
-
-    for y in 0..cfg.size.1 {
-        for x in 0..cfg.size.0 {
-            let val = NoiseValue {
-                value: noise.get_noise_u32(x, y),
-                cell: y * cfg.size.0 + x,
-            };
-            if heap.len() < cfg.obstacles {
-                heap.push(val)
-            } else if heap.peek().unwrap().value > val.value {
-                heap.pop();
-                heap.push(val)
-            }
+fn gen_noise(cfg: &Config) -> Box<dyn NoiseSource> {
+    let kind = NoiseKind::from_name(cfg.noise_params.kind.as_str()).expect("Invalid noise kind");
+    let mode = FractalMode::from_name(cfg.noise_params.fractal.as_str()).expect("Invalid fractal mode");
+    if cfg.noise_params.warp.unwrap_or(0.0) < 0.0 {
+        panic!("Noise warp must not be negative");
+    }
+    return kind.create(Some(cfg.seed), FractalParams::new(cfg.noise_params.octaves, cfg.noise_params.persistence, cfg.noise_params.lacunarity, cfg.noise_params.amplitude, cfg.noise_params.frequency, cfg.noise_params.cell_size, cfg.noise_params.offset).with_mode(mode, cfg.noise_params.warp));
+}
+
+/// Keeps the `n` smallest values seen
+fn push_smallest(heap: &mut BinaryHeap<NoiseValue>, val: NoiseValue, n: usize) {
+    if heap.len() < n {
+        heap.push(val)
+    } else if *heap.peek().unwrap() > val {
+        heap.pop();
+        heap.push(val)
+    }
+}
+
+fn gen_field_parameters(cfg: &Config) -> (u32, usize, Box<dyn NoiseSource>) {
+    let noise = gen_noise(cfg);
+
+    // every thread keeps the smallest values of its rows, merging them gives the smallest of the grid.
+    // Values are ordered by cell on ties, so the result does not depend on the number of threads
+    let heaps = fold_strips(noise.as_ref(), cfg.size, || BinaryHeap::with_capacity(cfg.obstacles), |heap, tile, values| {
+        for (i, value) in values.iter().enumerate() {
+            push_smallest(heap, NoiseValue { value: *value, cell: tile.y * tile.width + i }, cfg.obstacles);
         }
+    });
+    let mut heap: BinaryHeap<NoiseValue> = BinaryHeap::with_capacity(cfg.obstacles);
+    for val in heaps.into_iter().flatten() {
+        push_smallest(&mut heap, val, cfg.obstacles);
     }
 
     let v = heap.peek().unwrap();
     return (v.value, v.cell, noise);
 }
 
-fn gen_agents(cfg: &Config, field: &mut InstanceField) -> Vec<Agent> {
+fn gen_field(cfg: &Config) -> (InstanceField, Option<(u32, usize)>) {
+    let (field, noise_limits) = gen_base_field(cfg);
+    if let Some(overlay) = cfg.overlay.as_ref() {
+        if let Some(c) = overlay.add.iter().chain(overlay.remove.iter()).find(|c| !field.exists(c.0, c.1)) {
+            panic!("Overlay cell {:?} is outside the {}x{} grid", c, field.width(), field.height());
+        }
+        return (CompositeField::build(field, overlay.add.clone(), overlay.remove.clone()), noise_limits);
+    }
+    return (field, noise_limits);
+}
+
+fn gen_base_field(cfg: &Config) -> (InstanceField, Option<(u32, usize)>) {
+    if let Some(maze) = cfg.maze {
+        return (MazeField::generate(cfg.seed, cfg.size, maze), None);
+    }
+    if let Some(dungeon) = cfg.dungeon {
+        return (DungeonField::generate(cfg.seed, cfg.size, dungeon), None);
+    }
+    if let Some(warehouse) = cfg.warehouse {
+        return (WarehouseField::generate(cfg.seed, cfg.size, warehouse), None);
+    }
+
+    // First generate the nth minimum value coming from the noise generator seeded.
+    // We need to keep track of the last occurrence of the nth-minimum cell
+    let (limit, cell, noise) = gen_field_parameters(cfg);
+
+    //configure the field, materialized so that agents generation and aux do not re-evaluate the noise
+    let field = RandomField::with_terrain(noise, limit, cell, cfg.size, cfg.obstacles, cfg.noise_params.terrain_weight.unwrap_or(0.0)).materialize();
+    return (field, Some((limit, cell)));
+}
+
+fn gen_behaviour(cfg: &Config, field: &mut InstanceField, model: BehaviourModel, components: Option<&Rc<Components>>, index: usize, start: (usize, usize)) -> Box<dyn Behaviour> {
+    let behaviour: Box<dyn Behaviour> = match (model, components) {
+        (BehaviourModel::Destination, Some(c)) => Box::new(Destination::new(c.clone())),
+        (BehaviourModel::Patrol, Some(c)) if cfg.agents.waypoints.is_empty() => {
+            let mut waypoints = Vec::with_capacity(cfg.agents.patrol_points);
+            for _ in 0..cfg.agents.patrol_points {
+                let excluded = waypoints.iter().cloned().collect();
+                match field.rnd_pick_where(&excluded, |p| c.connected(start, p)) {
+                    Some(p) => waypoints.push(p),
+                    None => break
+                }
+            }
+            Box::new(Patrol::new(waypoints, 0))
+        }
+        (BehaviourModel::Patrol, Some(c)) => {
+            // unreachable waypoints are skipped, the agents spread along the shared cycle
+            let waypoints = cfg.agents.waypoints.iter().cloned().filter(|p| c.connected(start, *p)).collect::<Vec<_>>();
+            Box::new(Patrol::new(waypoints, index))
+        }
+        _ => Box::new(RandomWalk)
+    };
+    if cfg.agents.wait_probability > 0.0 {
+        return Box::new(Lazy::new(behaviour, cfg.agents.wait_probability));
+    }
+    return behaviour;
+}
+
+fn gen_agents(cfg: &Config, field: &mut InstanceField, region: &SpawnRegion) -> Vec<Agent> {
+    let model = BehaviourModel::from_name(cfg.agents.model.as_str()).expect("Invalid agent model");
+    let end = PathEnd::from_name(cfg.agents.end.as_str()).expect("Invalid agents path end");
+    if let Some(c) = cfg.agents.waypoints.iter().find(|c| !field.exists(c.0, c.1) || field.is_obstacle(c.0, c.1)) {
+        panic!("Waypoint {:?} is not a free cell of the grid", c);
+    }
+    // destinations and waypoints are kept in the agent's connected component
+    let components = match model {
+        BehaviourModel::Random => None,
+        _ => Some(Rc::new(Components::new(field)))
+    };
     let mut agents: Vec<Agent> = Vec::with_capacity(cfg.agents.number);
     let mut last_agent_positions: HashSet<(usize, usize)> = HashSet::with_capacity(cfg.agents.number);
-    for i in 0..cfg.agents.number {
-        let position = field.rnd_pick(&last_agent_positions).expect("Error during the creation of the agent");
-        // let pos = last_agent_positions.binary_search(&position).unwrap_or_else(|e| e);
-        last_agent_positions.insert(position);
-        agents.push(Agent::new(
-                cfg.seed + i as u64,
-                position,
-            )
-        )
+    let base = match field.kind() {
+        FieldKind::CompositeField(c) => c.base(),
+        _ => &*field
+    };
+    let staging_area = match base.kind() {
+        FieldKind::WarehouseField(w) => Some((*w.params(), (field.width(), field.height()))),
+        _ if cfg.agents.staging => panic!("Staging areas are only defined for warehouse fields"),
+        _ => None
+    };
+    let staging = |p: (usize, usize)| staging_area.is_none_or(|(w, size)| w.is_staging(size, p.0, p.1));
+    let lifetime = cfg.agents.lifetime;
+    if end == PathEnd::Loop && (cfg.agents.arrival_interval > 0 || lifetime.is_some()) {
+        panic!("Looping agents are on the map for the whole instance, they cannot arrive late or leave");
     }
 
-    for _t in 1..cfg.time_max {
+    // Agents move one at a time, avoiding the cells taken at the new tick by the ones that
+    // already moved and at the previous tick by the ones still to move. This rules out vertex
+    // conflicts, and swaps too: the cell of an agent still to move is never entered.
+    // Leaving agents free their cell before the others move, arriving ones take a cell free
+    // at the previous tick before the others move, so nobody enters it.
+    // Looping agents walk half of the time and retrace their steps, with a common period.
+    // Slow agents only step every `period` ticks from their arrival, staying on their cell
+    let period = cfg.agents.period.max(1);
+    let ticks = if end == PathEnd::Loop { cfg.time_max / 2 + 1 } else { cfg.time_max };
+    for t in 0..ticks {
+        for a in agents.iter().filter(|a| a.until() == Some(t)) {
+            last_agent_positions.remove(&a.get_last_pos());
+        }
+        while agents.len() < cfg.agents.number && agents.len() * cfg.agents.arrival_interval == t {
+            let i = agents.len();
+            let position = field.rnd_pick_where(&last_agent_positions, |p| region.contains(p) && (!cfg.agents.staging || staging(p))).expect("Error during the creation of the agent");
+            last_agent_positions.insert(position);
+            let behaviour = gen_behaviour(cfg, field, model, components.as_ref(), i, position);
+            agents.push(Agent::with_behaviour(
+                    cfg.seed + i as u64,
+                    position,
+                    behaviour,
+                ).with_window(t, lifetime.map(|l| t + l)).with_period(period)
+            )
+        }
         for i in 0..agents.len() {
             let a = agents.get_mut(i).unwrap();
+            if a.start() == t || a.until().is_some_and(|u| t >= u) || (t - a.start()) % period != 0 {
+                continue;
+            }
             last_agent_positions.remove(&a.get_last_pos());
             a.next_move(field, &last_agent_positions, cfg.agents.stop_probability);
             last_agent_positions.insert(a.get_last_pos());
         }
     }
+    if agents.len() < cfg.agents.number {
+        eprintln!("Only {} of {} agents arrive before the time limit", agents.len(), cfg.agents.number);
+    }
+    if end == PathEnd::Loop {
+        let steps = (ticks - 1) / period + 1;
+        agents.iter_mut().for_each(|a| a.retrace(steps));
+    }
+    let agents = agents.into_iter().map(|a| a.with_end(end)).collect::<Vec<Agent>>();
+    // checked before anything is written, so that no conflicting instance ever reaches the disk
+    if let Err(e) = validate_paths(field, &agents, cfg.time_max) {
+        eprintln!("Generated agents conflict, {}", e);
+        std::process::exit(1);
+    }
     return agents;
 }
 
-fn gen_entity_positions(field: &mut InstanceField, agents: &Vec<Agent>) -> ((usize, usize), (usize, usize)) {
+/// Positions where the planned agent's footprint would cover a `taken` cell or leave the free ones
+fn footprint_blocked(field: &InstanceField, taken: HashSet<(usize, usize)>) -> HashSet<(usize, usize)> {
+    if field.footprint() == (1, 1) {
+        return taken;
+    }
+    return (0..field.height()).flat_map(|y| (0..field.width()).map(move |x| (x, y)))
+        .filter(|p| !field.fits(p.0, p.1) || footprint_cells(*p, field.footprint()).any(|c| taken.contains(&c)))
+        .collect();
+}
+
+fn gen_entity_positions(field: &mut InstanceField, agents: &Vec<Agent>, region: &SpawnRegion) -> ((usize, usize), (usize, usize)) {
 
-    let start_positions = get_agents_at_time(&agents, 0); //should be already ordered
-    let init = field.rnd_pick(&start_positions).expect("Cannot pick starting position. grid is occupied at time 0");
+    let start_positions = footprint_blocked(field, get_agents_at_time(agents, 0)); //should be already ordered
+    let init = field.rnd_pick_where(&start_positions, |p| region.contains(p)).expect("Cannot pick starting position. grid is occupied at time 0");
 
-    let mut occupied_end_positions = get_agents_last(&agents);
+    let mut occupied_end_positions = footprint_blocked(field, get_agents_last(agents));
     occupied_end_positions.insert(init); //Theoretically we could start and end in the same position
 
-    let goal = field.rnd_pick(&occupied_end_positions).expect("Cannot pick starting position. grid is occupied at time 0");
+    let goal = field.rnd_pick_where(&occupied_end_positions, |p| region.contains(p) && region.connected(init, p)).expect("Cannot pick goal position. no free cell reachable from init");
     return (init, goal);
 
 }
@@ -92,7 +244,7 @@ fn compute_aux(field: &InstanceField, goal: (usize, usize), path: &str, tmax: us
             continue;
         }
         for adj in field.iter_neighbors(element.node().0, element.node().1) {
-            let cur_weight = nodes.get(element.node()).cloned().expect("Node never reached").0 + weight(element.node(), &adj);
+            let cur_weight = nodes.get(element.node()).cloned().expect("Node never reached").0 + field.move_weight(element.node(), &adj);
             let (dest_weight, _) = nodes.get(&adj).cloned().unwrap_or((f64::MAX, None));
             if cur_weight < dest_weight {
                 nodes.insert(adj, (cur_weight, Some(element.node()).cloned()));
@@ -109,27 +261,31 @@ fn compute_aux(field: &InstanceField, goal: (usize, usize), path: &str, tmax: us
     bincode::encode_into_std_write(&nodes, &mut e, config).expect("Cannot serialize");
 }
 
-fn write_results(agents: &Vec<Agent>, cfg: &Config, init: (usize, usize), goal: (usize, usize), limit: u32, limit_cell: usize) {
-    let out = OutSettings::new(agents, cfg, init, goal, limit, limit_cell);
+fn write_results(agents: &Vec<Agent>, cfg: &Config, init: (usize, usize), goal: (usize, usize), field: &InstanceField, noise_limits: Option<(u32, usize)>) {
+    let out = OutSettings::new(agents, cfg, init, goal, field, noise_limits);
     serde_yaml::to_writer(std::io::stdout(), &out).unwrap();
 }
 
 fn main() {
     let cfg = Config::load(None);
 
-    // First generate the nth minimum value coming from the noise generator seeded.
-    // We need to keep track of the last occurrence of the nth-minimum cell
-    let (limit, cell, noise) = gen_field_parameters(&cfg);
+    let (mut field, noise_limits) = gen_field(&cfg);
+    field.set_connectivity(Connectivity::from_neighbors(cfg.connectivity).expect("Invalid grid connectivity"));
+    field.set_corner_cutting(CornerCutting::from_name(cfg.corner_cutting.as_str()).expect("Invalid corner cutting policy"));
+    field.set_wrap(cfg.wrap).expect("Invalid grid wrap");
 
-    //configure the field
-    let mut field = RandomField::new(noise, limit, cell, cfg.size, cfg.obstacles);
+    let region = SpawnRegion::new(cfg.spawn.as_str(), &field);
 
     // Agents and start-finish can be recalculated based on the seed but
     // it's better to save the instance for more flexibility.
-    let agents = gen_agents(&cfg, &mut field);
+    let agents = gen_agents(&cfg, &mut field, &region);
+
+    // init, goal and aux follow the planned agent's footprint, the other agents take one cell
+    field.set_footprint(cfg.footprint);
+    let region = if field.footprint() == (1, 1) { region } else { SpawnRegion::new(cfg.spawn.as_str(), &field) };
 
     //get the randomly picked start and end positions
-    let (init, goal) = gen_entity_positions(&mut field, &agents);
+    let (init, goal) = gen_entity_positions(&mut field, &agents, &region);
 
     //precalculate the auxiliary table
     if let Some(path) = cfg.aux_path.as_ref() {
@@ -138,7 +294,18 @@ fn main() {
         }
     }
 
-    write_results(&agents, &cfg, init, goal, limit, cell);
+    if let Some(path) = cfg.map_path.as_ref() {
+        let mut file = File::create(path).expect("File creation error");
+        write_map(&field, &mut file).expect("Cannot write map");
+    }
+    if let Some(path) = cfg.image_path.as_ref() {
+        GrayImage::from_field(&field).save(path.as_str()).expect("Cannot write field image");
+    }
+    if let Some(path) = cfg.noise_image_path.as_ref() {
+        GrayImage::from_noise(gen_noise(&cfg).as_ref(), cfg.size).save(path.as_str()).expect("Cannot write noise image");
+    }
+
+    write_results(&agents, &cfg, init, goal, &field, noise_limits);
     if cfg.size.0 <= 300 && cfg.size.1 <= 300{
         eprintln!("{}", field);
     }
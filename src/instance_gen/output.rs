@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 
 use common::agent::agent::Agent;
+use common::field::field::{Field, InstanceField};
+use common::noise::NOISE_FORMAT_VERSION;
 
 use crate::args::Config;
 
@@ -16,6 +18,7 @@ pub struct OutSettings {
     time_max: usize,
     init: (usize, usize),
     goal: (usize, usize),
+    footprint: (usize, usize),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -23,12 +26,27 @@ pub struct OutGridSettings {
     width: usize,
     height: usize,
     obstacles: usize,
+    connectivity: usize,
+    corner_cutting: String,
+    wrap: bool,
     noise: Option<OutNoiseSettings>,
     custom: Option<Vec<(usize, usize)>>,
+    maze: Option<OutMazeSettings>,
+    dungeon: Option<OutDungeonSettings>,
+    warehouse: Option<OutWarehouseSettings>,
+    overlay: Option<OutOverlaySettings>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OutOverlaySettings {
+    add: Vec<(usize, usize)>,
+    remove: Vec<(usize, usize)>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct OutNoiseSettings {
+    version: u32,
+    kind: String,
     octaves: usize,
     persistence: f64,
     lacunarity: f64,
@@ -37,16 +55,47 @@ struct OutNoiseSettings {
     cell_size: usize,
     val_limit: u32,
     cell_limit: usize,
-    offset: f64
+    offset: f64,
+    terrain_weight: f64,
+    fractal: String,
+    warp: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OutMazeSettings {
+    algorithm: String,
+    corridor_width: usize,
+    braid: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OutDungeonSettings {
+    rooms: usize,
+    room_width: (usize, usize),
+    room_height: (usize, usize),
+    corridor_width: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OutWarehouseSettings {
+    rows: usize,
+    columns: usize,
+    shelf: (usize, usize),
+    aisle_width: usize,
+    margin: usize,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct OutAgentsSettings {
     paths: Vec<Vec<(usize, usize)>>,
+    end: String,
+    start_times: Option<Vec<usize>>,
+    end_times: Option<Vec<Option<usize>>>,
+    periods: Option<Vec<usize>>,
 }
 
 impl OutSettings {
-    pub fn new(agents: &Vec<Agent>, cfg: &Config, init: (usize, usize), goal: (usize, usize), limit: u32, limit_cell: usize) -> Self {
+    pub fn new(agents: &[Agent], cfg: &Config, init: (usize, usize), goal: (usize, usize), field: &InstanceField, noise_limits: Option<(u32, usize)>) -> Self {
         return OutSettings {
             seed: cfg.seed,
             id: cfg.id.clone(),
@@ -56,11 +105,17 @@ impl OutSettings {
             greedy: cfg.greedy,
             init,
             goal,
+            footprint: field.footprint(),
             grid: OutGridSettings {
                 width: cfg.size.0,
                 height: cfg.size.1,
-                obstacles: cfg.obstacles,
-                noise: Some(OutNoiseSettings {
+                obstacles: field.obstacles(),
+                connectivity: cfg.connectivity,
+                corner_cutting: cfg.corner_cutting.clone(),
+                wrap: cfg.wrap,
+                noise: noise_limits.map(|(limit, limit_cell)| OutNoiseSettings {
+                    version: NOISE_FORMAT_VERSION,
+                    kind: cfg.noise_params.kind.clone(),
                     octaves: cfg.noise_params.octaves.unwrap_or(1),
                     persistence: cfg.noise_params.persistence.unwrap_or(0.5),
                     lacunarity: cfg.noise_params.lacunarity.unwrap_or(2.0),
@@ -69,12 +124,41 @@ impl OutSettings {
                     cell_size: cfg.noise_params.cell_size.unwrap_or(100),
                     val_limit: limit,
                     cell_limit: limit_cell,
-                    offset: cfg.noise_params.offset.unwrap_or(0.1)
+                    offset: cfg.noise_params.offset.unwrap_or(0.1),
+                    terrain_weight: cfg.noise_params.terrain_weight.unwrap_or(0.0),
+                    fractal: cfg.noise_params.fractal.clone(),
+                    warp: cfg.noise_params.warp.unwrap_or(0.0),
                 }),
                 custom: None,
+                maze: cfg.maze.map(|m| OutMazeSettings {
+                    algorithm: m.algorithm.name().to_string(),
+                    corridor_width: m.corridor_width,
+                    braid: m.braid,
+                }),
+                dungeon: cfg.dungeon.map(|d| OutDungeonSettings {
+                    rooms: d.rooms,
+                    room_width: d.room_width,
+                    room_height: d.room_height,
+                    corridor_width: d.corridor_width,
+                }),
+                warehouse: cfg.warehouse.map(|w| OutWarehouseSettings {
+                    rows: w.rows,
+                    columns: w.columns,
+                    shelf: w.shelf,
+                    aisle_width: w.aisle_width,
+                    margin: w.margin,
+                }),
+                overlay: cfg.overlay.as_ref().map(|o| OutOverlaySettings {
+                    add: o.add.clone(),
+                    remove: o.remove.clone(),
+                }),
             },
             agents: OutAgentsSettings {
-                paths: agents.iter().map(|x| x.get_moves().clone()).collect::<Vec<Vec<(usize, usize)>>>()
+                paths: agents.iter().map(|x| x.get_moves().clone()).collect::<Vec<Vec<(usize, usize)>>>(),
+                end: cfg.agents.end.clone(),
+                start_times: if cfg.agents.arrival_interval > 0 { Some(agents.iter().map(|x| x.start()).collect()) } else { None },
+                end_times: cfg.agents.lifetime.map(|_| agents.iter().map(|x| x.until()).collect()),
+                periods: if cfg.agents.period > 1 { Some(agents.iter().map(|x| x.period()).collect()) } else { None },
             },
         };
     }
@@ -0,0 +1,41 @@
+use common::field::components::Components;
+use common::field::field::InstanceField;
+
+/// Restricts where agents, init and goal can be placed
+pub struct SpawnRegion {
+    components: Option<Components>,
+    component: Option<usize>,
+}
+
+impl SpawnRegion {
+    /// `policy` is one of
+    ///  - `any`: every free cell
+    ///  - `connected`: every free cell, but the goal must be reachable from init
+    ///  - `largest`: only the cells of the largest connected component
+    pub fn new(policy: &str, field: &InstanceField) -> Self {
+        return match policy {
+            "any" => SpawnRegion { components: None, component: None },
+            "connected" => SpawnRegion { components: Some(Components::new(field)), component: None },
+            "largest" => {
+                let components = Components::new(field);
+                let largest = components.largest();
+                SpawnRegion { components: Some(components), component: largest }
+            }
+            p => panic!("Unknown spawn policy '{}', expected any, connected or largest", p)
+        };
+    }
+
+    pub fn contains(&self, pos: (usize, usize)) -> bool {
+        return match (self.components.as_ref(), self.component) {
+            (Some(c), Some(l)) => c.label(pos.0, pos.1) == Some(l),
+            _ => true
+        };
+    }
+
+    pub fn connected(&self, a: (usize, usize), b: (usize, usize)) -> bool {
+        return match self.components.as_ref() {
+            Some(c) => c.connected(a, b),
+            None => true
+        };
+    }
+}
@@ -1,8 +1,10 @@
 use std::fs;
 
-use argparse::{ArgumentParser, StoreOption};
+use argparse::{ArgumentParser, Store, StoreOption, StoreTrue};
 use serde::{Deserialize, Serialize};
 
+use common::field::movingai::load_scen;
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "kind", rename_all = "snake_case")]
 pub enum ConfigTypes {
@@ -21,8 +23,16 @@ pub struct Config {
     pub aux_path: Option<String>,
     pub agents: AgentsConfig,
     pub time_max: usize,
-    pub init: (usize, usize),
-    pub goal: (usize, usize),
+    /// May be omitted when marked in the ascii grid
+    pub init: Option<(usize, usize)>,
+    pub goal: Option<(usize, usize)>,
+    /// Ticks the planned agent takes to reach a neighbouring cell, 1 when missing
+    pub move_ticks: Option<usize>,
+    /// `(width, height)` cells taken by the planned agent, anchored at its position, 1x1 when missing
+    pub footprint: Option<(usize, usize)>,
+    /// Only check the agents' paths, set from the command line
+    #[serde(skip)]
+    pub validate: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -32,10 +42,70 @@ pub struct GridConfig {
     pub obstacles: usize,
     pub noise: Option<NoiseConfig>,
     pub custom: Option<Vec<(usize, usize)>>,
+    pub terrain: Option<Vec<(usize, usize, f64)>>,
+    pub map: Option<String>,
+    /// Inline `#`/`.` map, see `common::field::ascii`
+    pub ascii: Option<String>,
+    pub image: Option<ImageConfig>,
+    pub connectivity: Option<usize>,
+    pub corner_cutting: Option<String>,
+    pub wrap: Option<bool>,
+    pub closures: Option<Vec<ClosureConfig>>,
+    pub maze: Option<MazeConfig>,
+    pub dungeon: Option<DungeonConfig>,
+    pub warehouse: Option<WarehouseConfig>,
+    pub overlay: Option<OverlayConfig>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OverlayConfig {
+    pub add: Option<Vec<(usize, usize)>>,
+    pub remove: Option<Vec<(usize, usize)>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImageConfig {
+    /// PGM or PNG image, the pixels darker than `threshold` are obstacles
+    pub path: String,
+    pub threshold: Option<u8>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WarehouseConfig {
+    pub rows: usize,
+    pub columns: usize,
+    pub shelf: (usize, usize),
+    pub aisle_width: usize,
+    pub margin: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DungeonConfig {
+    pub rooms: usize,
+    pub room_width: (usize, usize),
+    pub room_height: (usize, usize),
+    pub corridor_width: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MazeConfig {
+    pub algorithm: String,
+    pub corridor_width: usize,
+    pub braid: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClosureConfig {
+    pub cell: (usize, usize),
+    pub from: usize,
+    pub to: usize,
+    pub period: Option<usize>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct NoiseConfig {
+    pub version: Option<u32>,
+    pub kind: Option<String>,
     pub octaves: usize,
     pub persistence: f64,
     pub lacunarity: f64,
@@ -44,27 +114,62 @@ pub struct NoiseConfig {
     pub cell_size: usize,
     pub val_limit: u32,
     pub cell_limit: usize,
-    pub offset: f64
+    pub offset: f64,
+    pub terrain_weight: Option<f64>,
+    pub fractal: Option<String>,
+    pub warp: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AgentsConfig {
     pub paths: Vec<Vec<(usize, usize)>>,
+    /// What agents do once their path is over: park (default), vanish or loop
+    pub end: Option<String>,
+    /// Per agent override of `end`, aligned with `paths`
+    pub ends: Option<Vec<String>>,
+    /// Tick each agent enters the map at on the first cell of its path, 0 when missing
+    pub start_times: Option<Vec<usize>>,
+    /// Tick each agent leaves the map at, null to stay until its path end says otherwise
+    pub end_times: Option<Vec<Option<usize>>>,
+    /// Ticks between two steps of each agent's path, waiting on its cell in between, 1 when missing
+    pub periods: Option<Vec<usize>>,
+    /// Ticks each agent takes to reach the next cell of its path, taking both, 1 when missing
+    pub move_ticks: Option<Vec<usize>>,
+    /// `(width, height)` cells taken by each agent, anchored at its position, 1x1 when missing
+    pub footprints: Option<Vec<(usize, usize)>>,
 }
 
 impl Config {
     pub fn load() -> Self {
         let mut fname: Option<String> = None;
         let mut conf_id: Option<String> = None;
+        let mut map: Option<String> = None;
+        let mut scen: Option<String> = None;
+        let mut scen_index: usize = 0;
+        let mut time_max: usize = 1000;
+        let mut validate = false;
         {
             let mut ap = ArgumentParser::new();
             ap.set_description("Instance Solver");
 
             ap.refer(&mut fname).add_option(&["-c", "--config"], StoreOption, "Config file name. If present configuration will be loaded from file INSTEAD of cmdline.");
             ap.refer(&mut conf_id).add_option(&["-i", "--config-id"], StoreOption, "Config ID. Allows to load one doc from the yaml file");
+            ap.refer(&mut map).add_option(&["-m", "--map"], StoreOption, "MovingAI .map file. Used when no config file is given");
+            ap.refer(&mut scen).add_option(&["-s", "--scen"], StoreOption, "MovingAI .scen file providing init and goal");
+            ap.refer(&mut scen_index).add_option(&["-n", "--scen-index"], Store, "Row of the .scen file to solve");
+            ap.refer(&mut time_max).add_option(&["-t", "--tmax"], Store, "Maximum resolution time depth");
+            ap.refer(&mut validate).add_option(&["--validate"], StoreTrue, "Check the agents paths for conflicts instead of solving");
             ap.parse_args_or_exit();
         }
 
+        if fname.is_none() {
+            if let Some(map) = map {
+                let mut cfg = Config::from_movingai(map, scen.expect("A .scen file is required along with the map"), scen_index, time_max);
+                cfg.validate = validate;
+                return cfg;
+            }
+        }
+
         let contents = fs::read_to_string(fname.expect("File not specified")).expect("Should have been able to read the file");
 
         for document in serde_yaml::Deserializer::from_str(contents.as_str()) {
@@ -77,7 +182,7 @@ impl Config {
                                     continue;
                                 }
                             }
-                            config
+                            Config { validate, ..config }
                         }
                     };
                 }
@@ -89,4 +194,43 @@ impl Config {
 
         panic!("Cannot read a valid config")
     }
-}
\ No newline at end of file
+
+    fn from_movingai(map: String, scen: String, index: usize, time_max: usize) -> Self {
+        let entries = load_scen(scen.as_str()).expect("Cannot load scenario");
+        let entry = entries.get(index).expect("Scenario index out of range");
+
+        return Config {
+            id: format!("{}#{}", scen, index),
+            seed: 0,
+            greedy: true,
+            grid: GridConfig {
+                width: entry.width,
+                height: entry.height,
+                obstacles: 0,
+                noise: None,
+                custom: None,
+                terrain: None,
+                map: Some(map),
+                ascii: None,
+                image: None,
+                connectivity: None,
+                // the benchmark optimal lengths never cut corners
+                corner_cutting: Some("forbid_if_either".into()),
+                wrap: None,
+                closures: None,
+                maze: None,
+                dungeon: None,
+                warehouse: None,
+                overlay: None,
+            },
+            aux_path: None,
+            agents: AgentsConfig { paths: vec![], end: None, ends: None, start_times: None, end_times: None, periods: None, move_ticks: None, footprints: None },
+            time_max,
+            init: Some(entry.init),
+            goal: Some(entry.goal),
+            move_ticks: None,
+            footprint: None,
+            validate: false,
+        };
+    }
+}
@@ -5,13 +5,24 @@ use std::fs::File;
 use bincode::error::DecodeError;
 use flate2::read::ZlibDecoder;
 
-use common::agent::agent::Agent;
+use common::agent::agent::{Agent, PathEnd};
 use common::agent::agent_manager::AgentManager;
-use common::field::{heuristic, weight};
-use common::field::field::{CustomField, InstanceField, RandomField};
+use common::agent::validate::validate_paths;
+use common::field::heuristic;
+use common::field::closure::{Closure, Closures};
+use common::field::ascii::{parse_ascii, AsciiMap, AsciiMarkers};
+use common::field::composite::CompositeField;
+use common::field::connectivity::{Connectivity, CornerCutting};
+use common::field::field::{CustomField, Field, InstanceField, RandomField};
+use common::field::dungeon::{DungeonField, DungeonParams};
+use common::field::image::{load_image, DEFAULT_THRESHOLD};
+use common::field::maze::{MazeAlgorithm, MazeField, MazeParams};
+use common::field::movingai::load_map;
+use common::field::warehouse::{WarehouseField, WarehouseParams};
 use common::field::open_node::OpenNode;
 use common::field::visited_node::VisitedNode;
-use common::noise::perlin::PerlinNoise;
+use common::noise::fractal::{FractalMode, FractalParams};
+use common::noise::{NoiseKind, NOISE_FORMAT_VERSION};
 
 use crate::args::Config;
 use crate::output::{Solution, SolutionPath};
@@ -21,10 +32,67 @@ mod output;
 
 type AuxMap = HashMap<(usize, usize), (f64, Option<(usize, usize)>)>;
 
-fn create_field_from_configs(cfg: &Config) -> Result<InstanceField, &str> {
+fn create_field_from_configs(cfg: &Config) -> Result<(InstanceField, AsciiMarkers), &str> {
+    let (mut field, markers) = create_base_field(cfg)?;
+    if let Some(overlay) = cfg.grid.overlay.as_ref() {
+        let added = overlay.add.clone().unwrap_or_default();
+        let removed = overlay.remove.clone().unwrap_or_default();
+        if let Some(c) = added.iter().chain(removed.iter()).find(|c| c.0 >= cfg.grid.width || c.1 >= cfg.grid.height) {
+            eprintln!("Overlay cell {:?} is outside the {}x{} grid", c, cfg.grid.width, cfg.grid.height);
+            return Err("Invalid grid overlay");
+        }
+        field = CompositeField::build(field, added, removed);
+    }
+    if let Some(neighbors) = cfg.grid.connectivity {
+        let connectivity = Connectivity::from_neighbors(neighbors).map_err(|e| {
+            eprintln!("{}", e);
+            "Invalid grid connectivity"
+        })?;
+        field.set_connectivity(connectivity);
+    }
+    if let Some(policy) = cfg.grid.corner_cutting.as_ref() {
+        let corner_cutting = CornerCutting::from_name(policy.as_str()).map_err(|e| {
+            eprintln!("{}", e);
+            "Invalid corner cutting policy"
+        })?;
+        field.set_corner_cutting(corner_cutting);
+    }
+    if cfg.grid.wrap.unwrap_or(false) {
+        field.set_wrap(true).map_err(|e| {
+            eprintln!("{}", e);
+            "Invalid grid wrap"
+        })?;
+    }
+    if let Some(footprint) = cfg.footprint {
+        field.set_footprint(footprint);
+    }
+    return Ok((field, markers));
+}
+
+/// The field and the positions marked on it, only ascii grids carry markers
+fn create_base_field(cfg: &Config) -> Result<(InstanceField, AsciiMarkers), &str> {
     if let Some(noise) = cfg.grid.noise.as_ref() {
-        let p_noise = PerlinNoise::new(
-            Some(cfg.seed),
+        // instances without a version predate the portable lattice hash
+        let version = noise.version.unwrap_or(1);
+        if version != NOISE_FORMAT_VERSION {
+            eprintln!("Instance noise format is version {}, this build decodes version {}. Regenerate the instance", version, NOISE_FORMAT_VERSION);
+            return Err("Incompatible noise format version");
+        }
+        let kind = NoiseKind::from_name(noise.kind.as_deref().unwrap_or("perlin")).map_err(|e| {
+            eprintln!("{}", e);
+            "Invalid noise kind"
+        })?;
+        let mode = FractalMode::from_name(noise.fractal.as_deref().unwrap_or("fbm")).map_err(|e| {
+            eprintln!("{}", e);
+            "Invalid fractal mode"
+        })?;
+        if noise.warp.unwrap_or(0.0) < 0.0 {
+            return Err("Noise warp must not be negative");
+        }
+        if noise.terrain_weight.unwrap_or(0.0) < 0.0 {
+            return Err("Noise terrain weight must not be negative");
+        }
+        let p_noise = kind.create(Some(cfg.seed), FractalParams::new(
             Some(noise.octaves),
             Some(noise.persistence),
             Some(noise.lacunarity),
@@ -32,16 +100,86 @@ fn create_field_from_configs(cfg: &Config) -> Result<InstanceField, &str> {
             Some(noise.frequency),
             Some(noise.cell_size),
             Some(noise.offset)
-        );
+        ).with_mode(mode, noise.warp));
+
+        // evaluate the noise once, the search only needs a lookup per cell
+        return Ok((RandomField::with_terrain(p_noise, noise.val_limit, noise.cell_limit, (cfg.grid.width, cfg.grid.height), cfg.grid.obstacles, noise.terrain_weight.unwrap_or(0.0)).materialize(), AsciiMarkers::default()));
+    }
+
+    if let Some(maze) = cfg.grid.maze.as_ref() {
+        let algorithm = MazeAlgorithm::from_name(maze.algorithm.as_str()).map_err(|e| {
+            eprintln!("{}", e);
+            "Invalid maze algorithm"
+        })?;
+        return Ok((MazeField::generate(cfg.seed, (cfg.grid.width, cfg.grid.height), MazeParams { algorithm, corridor_width: maze.corridor_width, braid: maze.braid }), AsciiMarkers::default()));
+    }
+
+    if let Some(dungeon) = cfg.grid.dungeon.as_ref() {
+        return Ok((DungeonField::generate(cfg.seed, (cfg.grid.width, cfg.grid.height), DungeonParams {
+            rooms: dungeon.rooms,
+            room_width: dungeon.room_width,
+            room_height: dungeon.room_height,
+            corridor_width: dungeon.corridor_width,
+        }), AsciiMarkers::default()));
+    }
+
+    if let Some(warehouse) = cfg.grid.warehouse.as_ref() {
+        return Ok((WarehouseField::generate(cfg.seed, (cfg.grid.width, cfg.grid.height), WarehouseParams {
+            rows: warehouse.rows,
+            columns: warehouse.columns,
+            shelf: warehouse.shelf,
+            aisle_width: warehouse.aisle_width,
+            margin: warehouse.margin,
+        }), AsciiMarkers::default()));
+    }
+
+    if let Some(ascii) = cfg.grid.ascii.as_ref() {
+        let AsciiMap { field, markers } = parse_ascii(ascii.as_str(), cfg.seed).map_err(|e| {
+            eprintln!("{}", e);
+            "Cannot parse the ascii grid"
+        })?;
+        if (field.width(), field.height()) != (cfg.grid.width, cfg.grid.height) {
+            eprintln!("Ascii grid is {}x{}, expected {}x{}", field.width(), field.height(), cfg.grid.width, cfg.grid.height);
+            return Err("Ascii grid size mismatch");
+        }
+        return Ok((field, markers));
+    }
 
-        return Ok(RandomField::new(p_noise, noise.val_limit, noise.cell_limit, (cfg.grid.width, cfg.grid.height), cfg.grid.obstacles));
+    if let Some(image) = cfg.grid.image.as_ref() {
+        let img = load_image(image.path.as_str()).map_err(|e| {
+            eprintln!("{}", e);
+            "Cannot load the grid image"
+        })?;
+        if (img.width, img.height) != (cfg.grid.width, cfg.grid.height) {
+            eprintln!("Grid image is {}x{}, expected {}x{}", img.width, img.height, cfg.grid.width, cfg.grid.height);
+            return Err("Grid image size mismatch");
+        }
+        return Ok((img.to_field(image.threshold.unwrap_or(DEFAULT_THRESHOLD), cfg.seed), AsciiMarkers::default()));
+    }
+
+    if let Some(map) = cfg.grid.map.as_ref() {
+        let field = load_map(map.as_str(), cfg.seed).map_err(|e| {
+            eprintln!("{}", e);
+            "Cannot load the MovingAI map"
+        })?;
+        if (field.width(), field.height()) != (cfg.grid.width, cfg.grid.height) {
+            eprintln!("MovingAI map is {}x{}, expected {}x{}", field.width(), field.height(), cfg.grid.width, cfg.grid.height);
+            return Err("MovingAI map size mismatch");
+        }
+        return Ok((field, AsciiMarkers::default()));
     }
 
     if let Some(obstacles) = cfg.grid.custom.clone() {
-        return Ok(CustomField::new(cfg.seed, (cfg.grid.width, cfg.grid.height), obstacles));
+        let terrain = cfg.grid.terrain.clone().unwrap_or_default();
+        // cheaper cells would make the heuristic overestimate
+        if let Some((x, y, cost)) = terrain.iter().find(|(_, _, cost)| cost.is_nan() || *cost < 1.0) {
+            eprintln!("Terrain cost of ({}, {}) is {}, expected at least 1", x, y, cost);
+            return Err("Terrain costs must be at least 1");
+        }
+        return Ok((CustomField::with_costs(cfg.seed, (cfg.grid.width, cfg.grid.height), obstacles, terrain), AsciiMarkers::default()));
     }
 
-    return Err("Cannot load the field. neither noise nor custom are defined in the settings");
+    return Err("Cannot load the field. neither noise, maze, dungeon, warehouse, custom nor map are defined in the settings");
 }
 
 fn load_aux(path: &str) -> Result<AuxMap, DecodeError> {
@@ -51,7 +189,9 @@ fn load_aux(path: &str) -> Result<AuxMap, DecodeError> {
     return bincode::decode_from_std_read(&mut zlib, config);
 }
 
-fn verify_path(path: &Vec<(usize, usize)>, t_start: usize, t_max: usize, agents: &AgentManager, goal: (usize, usize)) -> Result<(), ()> {
+/// `path` holds a cell per tick, a move taking `ticks` ticks being preceded by the
+/// `ticks - 1` ticks spent leaving its cell
+fn verify_path(path: &[(usize, usize)], t_start: usize, t_max: usize, agents: &AgentManager, goal: (usize, usize), ticks: usize) -> Result<(), ()> {
     if path.len() == 0 {
         return Ok(());
     }
@@ -67,7 +207,10 @@ fn verify_path(path: &Vec<(usize, usize)>, t_start: usize, t_max: usize, agents:
 
     while t_start + i <= t_max {
         let dest = path.get(i).cloned().expect("Path is shorter than time delta");
-        if !agents.is_traversable(pos, dest, t_start + i - 1) {
+        if dest != pos && (i < ticks || !agents.is_traversable_in(pos, dest, t_start + i - ticks, ticks)) {
+            return Err(());
+        }
+        if dest == pos && !agents.is_traversable(pos, dest, t_start + i - 1) {
             return Err(());
         }
         if i == path.len() - 1 {
@@ -86,7 +229,7 @@ fn verify_path(path: &Vec<(usize, usize)>, t_start: usize, t_max: usize, agents:
     return Err(());
 }
 
-fn reconstruct_path(nodes: &HashMap<(usize, usize), VisitedNode>, goal: (usize, usize)) -> SolutionPath {
+fn reconstruct_path(nodes: &HashMap<(usize, usize), VisitedNode>, goal: (usize, usize), ticks: usize) -> SolutionPath {
     let mut t = nodes.get(&goal).unwrap().best_time();
     let mut n = nodes.get(&goal);
     let mut queue = VecDeque::with_capacity(t);
@@ -98,9 +241,17 @@ fn reconstruct_path(nodes: &HashMap<(usize, usize), VisitedNode>, goal: (usize,
         queue.push_front(cur);
 
         if let Some(parent) = nxt.parent(t) {
-            if parent == cur { waits += 1; }
+            if parent == cur {
+                waits += 1;
+                t -= 1;
+            } else {
+                // still on the parent while moving to `cur`
+                for _ in 1..ticks {
+                    queue.push_front(parent);
+                }
+                t -= ticks;
+            }
             n = nodes.get(&parent);
-            t -= 1;
         } else {
             break;
         }
@@ -114,6 +265,20 @@ fn reconstruct_path(nodes: &HashMap<(usize, usize), VisitedNode>, goal: (usize,
     };
 }
 
+/// Cells of the leg from `from` along `path`, one per tick when every move takes `ticks` ticks
+fn stretch_path(from: (usize, usize), path: Vec<(usize, usize)>, ticks: usize) -> Vec<(usize, usize)> {
+    let mut stretched = Vec::with_capacity(path.len() * ticks + 1);
+    stretched.push(from);
+    for cell in path {
+        let pos = stretched.last().cloned().unwrap();
+        if cell != pos {
+            stretched.extend(std::iter::repeat_n(pos, ticks - 1));
+        }
+        stretched.push(cell);
+    }
+    return stretched;
+}
+
 fn get_path_from_aux(location: (usize, usize), aux: &AuxMap) -> Result<(Vec<(usize, usize)>, f64), ()> {
     let mut path = Vec::new();
     let reference = aux.get(&location).cloned();
@@ -129,8 +294,19 @@ fn get_path_from_aux(location: (usize, usize), aux: &AuxMap) -> Result<(Vec<(usi
     return Ok((path, w));
 }
 
-fn solve(field: &InstanceField, agents: &AgentManager, init: (usize, usize), goal: (usize, usize), tmax: usize, aux: Option<&AuxMap>, greedy: bool) -> Solution {
-    let min_cells = max((init.0 as i64 - goal.0 as i64).abs(), (init.1 as i64 - goal.1 as i64).abs()) as usize;
+/// How far the search goes and how the planned agent moves
+#[derive(Clone, Copy)]
+struct SearchParams {
+    tmax: usize,
+    greedy: bool,
+    /// Ticks taken by a move, waits take one
+    ticks: usize,
+}
+
+fn solve(field: &InstanceField, agents: &AgentManager, init: (usize, usize), goal: (usize, usize), aux: Option<&AuxMap>, params: &SearchParams) -> Solution {
+    let SearchParams { tmax, greedy, ticks } = *params;
+    let (dx, dy) = field.delta(&init, &goal);
+    let min_cells = max(dx.abs(), dy.abs()) as usize;
     let mut open: BinaryHeap<Reverse<OpenNode<(usize, usize)>>> = BinaryHeap::with_capacity(min_cells);
     let mut closed: HashSet<((usize, usize), usize)> = HashSet::with_capacity(min_cells);
     let mut nodes: HashMap<(usize, usize), VisitedNode> = HashMap::with_capacity(min_cells);
@@ -138,7 +314,7 @@ fn solve(field: &InstanceField, agents: &AgentManager, init: (usize, usize), goa
     let mut opened: usize = 1;
 
     //initialize first node
-    let mut first_node = VisitedNode::new(init); //init location
+    let mut first_node = VisitedNode::new(init, field.move_weight(&init, &init)); //init location
     first_node.set(0, 0.0, None, agents); //set parent to none and weight 0 at time 0
     nodes.insert(init, first_node); //store the visited node
     open.push(Reverse(OpenNode::new(0.0, init, 0)));
@@ -158,17 +334,20 @@ fn solve(field: &InstanceField, agents: &AgentManager, init: (usize, usize), goa
             let path = get_path_from_aux(node, aux_map);
             match path {
                 Ok(path) => {
-                    let (mut path, w) = path;
-                    if let Ok(_) = verify_path(&path, element.time() + 1, tmax, &agents, goal) {
-                        let mut prev_path = reconstruct_path(&nodes, node);
-                        prev_path.time += path.len();
+                    let (path, w) = path;
+                    let first = path.first().cloned();
+                    // the leg starts on `node`, its moves line up with the ticks of the search
+                    let leg = stretch_path(node, path, ticks);
+                    if verify_path(&leg, element.time(), tmax, &agents, goal, ticks).is_ok() {
+                        let mut prev_path = reconstruct_path(&nodes, node, ticks);
+                        prev_path.time += leg.len() - 1;
                         prev_path.weight += w;
-                        if path.len() > 0 {
-                            prev_path.weight += weight(&node, path.first().unwrap());
+                        if let Some(first) = first {
+                            prev_path.weight += field.move_weight(&node, &first);
                         }
-                        prev_path.path.append(&mut path);
+                        prev_path.path.extend_from_slice(&leg[1..]);
 
-                        if verify_path(&prev_path.path, 0, tmax, &agents, goal).is_ok() {
+                        if verify_path(&prev_path.path, 0, tmax, &agents, goal, ticks).is_ok() {
                             return Solution {
                                 kind: "solution".to_string(),
                                 opened_states: opened,
@@ -183,24 +362,27 @@ fn solve(field: &InstanceField, agents: &AgentManager, init: (usize, usize), goa
         }
 
         for neighbor in field.iter_neighbors(node.0, node.1) {
-            if !agents.is_traversable(node, neighbor, element.time()) { continue; }
+            // waits always take a single tick
+            let arrival = element.time() + if neighbor == node { 1 } else { ticks };
+            if arrival > tmax { continue; }
+            if !agents.is_traversable_in(node, neighbor, element.time(), arrival - element.time()) { continue; }
 
             if !nodes.contains_key(&neighbor) {
-                nodes.insert(neighbor, VisitedNode::new(neighbor));
+                nodes.insert(neighbor, VisitedNode::new(neighbor, field.move_weight(&neighbor, &neighbor)));
             }
             let dest_reference = nodes.get_mut(&neighbor).unwrap();
 
-            let weight = weight(&node, &neighbor);
-            let dst_weight = dest_reference.weight(element.time() + 1, agents);
-            if closed.contains(&(neighbor, element.time() + 1)) /*&& src_weight + weight >= dst_weight*/ { continue; }
+            let weight = field.move_weight(&node, &neighbor);
+            let dst_weight = dest_reference.weight(arrival, agents);
+            if closed.contains(&(neighbor, arrival)) /*&& src_weight + weight >= dst_weight*/ { continue; }
 
             if src_weight + weight < dst_weight {
-                dest_reference.set(element.time() + 1, src_weight + weight, Some(node), agents);
+                dest_reference.set(arrival, src_weight + weight, Some(node), agents);
             }
 
-            if open.iter().filter(|x| x.0.node().clone() == neighbor && x.0.time() == element.time() + 1).count() == 0 {
+            if open.iter().filter(|x| x.0.node().clone() == neighbor && x.0.time() == arrival).count() == 0 {
                 opened += 1;
-                open.push(Reverse(OpenNode::new(heuristic(&neighbor, &goal) + dest_reference.weight(element.time() + 1, agents), neighbor, element.time() + 1)));
+                open.push(Reverse(OpenNode::new(heuristic(field, &neighbor, &goal) + dest_reference.weight(arrival, agents), neighbor, arrival)));
             }
         }
     }
@@ -214,8 +396,8 @@ fn solve(field: &InstanceField, agents: &AgentManager, init: (usize, usize), goa
         };
     }
 
-    let path = reconstruct_path(&nodes, goal);
-    if verify_path(&path.path, 0, tmax, &agents, goal).is_err(){
+    let path = reconstruct_path(&nodes, goal, ticks);
+    if verify_path(&path.path, 0, tmax, &agents, goal, ticks).is_err(){
         return Solution {
             kind: "error".to_string(),
             opened_states: opened,
@@ -235,17 +417,56 @@ fn main() {
     let cfg = Config::load();
 
     // First of all create the field
-    let field = create_field_from_configs(&cfg).expect("Cannot create field");
+    let (field, markers) = create_field_from_configs(&cfg).expect("Cannot create field");
     if cfg.grid.width <= 200 && cfg.grid.height <= 200{
         eprintln!("{}", field);
     }
 
-    //then create the agents
-    let mut agents = Vec::with_capacity(cfg.agents.paths.len());
-    for a in cfg.agents.paths {
-        agents.push(Agent::from(a));
+    // positions marked in the ascii grid, explicit ones take precedence
+    let init = cfg.init.or(markers.init).expect("Instance has no init position");
+    let goal = cfg.goal.or(markers.goal).expect("Instance has no goal position");
+    if let Some(p) = [init, goal].into_iter().find(|p| field.footprint() != (1, 1) && !field.fits(p.0, p.1)) {
+        panic!("The {:?} footprint anchored at {:?} does not lie on free cells", field.footprint(), p);
     }
-    let mgr = AgentManager::new(agents);
+
+    //then create the agents, the ones marked in the ascii grid never move
+    let end = PathEnd::from_name(cfg.agents.end.as_deref().unwrap_or("park")).expect("Invalid agents path end");
+    let ends = cfg.agents.ends.unwrap_or_default();
+    let start_times = cfg.agents.start_times.unwrap_or_default();
+    let end_times = cfg.agents.end_times.unwrap_or_default();
+    let periods = cfg.agents.periods.unwrap_or_default();
+    let move_ticks = cfg.agents.move_ticks.unwrap_or_default();
+    let footprints = cfg.agents.footprints.unwrap_or_default();
+    if ends.len().max(start_times.len()).max(end_times.len()).max(periods.len()).max(move_ticks.len()).max(footprints.len()) > cfg.agents.paths.len() {
+        panic!("More path ends, times, periods, move ticks or footprints than the {} agents", cfg.agents.paths.len());
+    }
+    let mut agents = Vec::with_capacity(cfg.agents.paths.len() + markers.agents.len());
+    for (i, a) in cfg.agents.paths.into_iter().enumerate() {
+        let end = ends.get(i).map_or(Ok(end), |e| PathEnd::from_name(e.as_str())).expect("Invalid agent path end");
+        let window = (start_times.get(i).cloned().unwrap_or(0), end_times.get(i).cloned().flatten());
+        let period = periods.get(i).cloned().unwrap_or(1);
+        let ticks = move_ticks.get(i).cloned().unwrap_or(1);
+        let footprint = footprints.get(i).cloned().unwrap_or((1, 1));
+        agents.push(Agent::from(a).with_end(end).with_window(window.0, window.1).with_period(period).with_move_ticks(ticks).with_footprint(footprint));
+    }
+    for start in markers.agents {
+        agents.push(Agent::from(vec![start]));
+    }
+    // hand written instances may park agents outside the grid, so conflicts are only fatal when validating
+    match validate_paths(&field, &agents, cfg.time_max) {
+        Err(e) if cfg.validate => panic!("Invalid agents paths, {}", e),
+        Err(e) => eprintln!("Warning: invalid agents paths, {}", e),
+        Ok(()) if cfg.validate => {
+            eprintln!("{} agents paths are valid", agents.len());
+            return;
+        }
+        Ok(()) => {}
+    }
+    let closures = cfg.grid.closures.unwrap_or_default().into_iter()
+        .map(|c| Closure { cell: c.cell, from: c.from, to: c.to, period: c.period })
+        .collect::<Vec<Closure>>();
+    let footprint = cfg.footprint.unwrap_or((1, 1));
+    let mgr = AgentManager::with_closures(agents, Closures::new(closures)).with_footprint(footprint);
 
     //load aux if present
     let mut aux: Option<AuxMap> = None;
@@ -255,7 +476,8 @@ fn main() {
         }
     }
 
-    let sol = solve(&field, &mgr, cfg.init, cfg.goal, cfg.time_max, aux.as_ref(), cfg.greedy);
+    let params = SearchParams { tmax: cfg.time_max, greedy: cfg.greedy, ticks: cfg.move_ticks.unwrap_or(1).max(1) };
+    let sol = solve(&field, &mgr, init, goal, aux.as_ref(), &params);
     serde_yaml::to_writer(std::io::stdout(), &sol).unwrap();
     eprintln!("GREEDY: {}", cfg.greedy);
     if let Some(pf) = sol.path_info{
@@ -265,4 +487,29 @@ fn main() {
         eprintln!("Waits: {}", pf.waits);
     }
     eprintln!("States (expanded)/(opened): {}/{}", sol.expanded_states, sol.opened_states);
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod solve_test {
+    use common::agent::agent_manager::AgentManager;
+    use common::field::field::CustomField;
+
+    use crate::{solve, AuxMap, SearchParams};
+
+    #[test]
+    fn aux_shortcut_with_slow_moves() {
+        // a corridor, the aux table leading right to the goal
+        let field = CustomField::new(42, (4, 1), vec![]);
+        let goal = (3, 0);
+        let aux = (0..4).map(|x| ((x, 0), ((3 - x) as f64, if x < 3 { Some((x + 1, 0)) } else { None })))
+            .collect::<AuxMap>();
+        let params = SearchParams { tmax: 20, greedy: true, ticks: 2 };
+        let sol = solve(&field, &AgentManager::new(vec![]), (0, 0), goal, Some(&aux), &params);
+
+        // taken straight from the first expanded node
+        assert_eq!(1, sol.expanded_states);
+        let path = sol.path_info.unwrap();
+        assert_eq!(vec![(0, 0), (0, 0), (1, 0), (1, 0), (2, 0), (2, 0), (3, 0)], path.path);
+        assert_eq!(6, path.time);
+    }
+}